@@ -0,0 +1,66 @@
+use super::{fields_from_input, FieldReceiver};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{DeriveInput, Result};
+
+pub(crate) fn derive_container_attributes(input: DeriveInput) -> Result<TokenStream> {
+    let (receiver, fields_information) = fields_from_input(&input)?;
+
+    let mut write_body = quote!();
+    let mut read_body = quote!();
+    let mut attributes = Vec::new();
+
+    for rx in &fields_information {
+        let field_name = rx.ident.clone().unwrap();
+        let field_type = &rx.ty;
+        let read_name = rx.rename.read_name_or_ident(&field_name);
+        let write_name = rx.rename.write_name_or_ident(&field_name);
+
+        write_body = quote!(
+            #write_body
+            hdf5_derive::AttributeValue::write_attribute(&self.#field_name, location, #write_name)?;
+        );
+
+        read_body = quote!(
+            #read_body
+            let #field_name = <#field_type as hdf5_derive::AttributeValue>::read_attribute(location, #read_name)?;
+        );
+
+        attributes.push(quote!(
+            hdf5_derive::schema::Attribute {
+                name: #write_name,
+                element_type: <#field_type as hdf5_derive::AttributeValue>::type_descriptor(),
+                value: None,
+            }
+        ));
+    }
+
+    let punct: Punctuated<syn::Ident, syn::Token![,]> = fields_information
+        .iter()
+        .map(|rx: &FieldReceiver| rx.ident.clone().unwrap())
+        .collect();
+
+    let (imp, ty, wher) = receiver.generics.split_for_impl();
+    let ident = receiver.ident.clone();
+
+    let output = quote!(
+        impl #imp hdf5_derive::ContainerAttributes for #ident #ty #wher {
+            fn write_attributes(&self, location: &hdf5_derive::Location) -> Result<(), hdf5_derive::Error> {
+                #write_body
+                Ok(())
+            }
+
+            fn read_attributes(location: &hdf5_derive::Location) -> Result<Self, hdf5_derive::Error> {
+                #read_body
+                Ok(#ident { #punct })
+            }
+
+            fn attributes() -> Vec<hdf5_derive::schema::Attribute> {
+                vec![#(#attributes),*]
+            }
+        }
+    );
+
+    Ok(output)
+}
@@ -0,0 +1,127 @@
+use super::read::{read_codegen, read_report_codegen, validate_codegen};
+use super::read::{write_complete_check, write_complete_report, write_complete_validate, ReadInfo};
+use super::{FieldReceiver, InputReceiver};
+use proc_macro2::Span;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Result;
+
+/// generate a struct named `twin_name` mirroring the container in `receiver`, where each field
+/// type `T` is replaced with `<T as LazyTwin>::Lazy`. The twin implements `ContainerRead`
+/// and has a `load` method that materializes the original container.
+pub(crate) fn lazy_twin_codegen(
+    receiver: &InputReceiver,
+    fields: &[FieldReceiver],
+    twin_name: &str,
+    span: Span,
+) -> Result<TokenStream> {
+    let twin = syn::Ident::new(twin_name, span);
+    let ident = &receiver.ident;
+    let vis = &receiver.vis;
+    let generics = &receiver.generics;
+    let (imp, ty, wher) = generics.split_for_impl();
+
+    let mut twin_fields = quote!();
+    let mut load_fields = quote!();
+    let mut read_data = Vec::new();
+
+    for rx in fields {
+        let field_name = rx.ident.clone().unwrap();
+        let stored_type = rx.stored_type();
+        let field_vis = &rx.vis;
+        let field_docs = &rx.attrs;
+        let transpose = rx.transpose.unwrap_or(receiver.transpose).transpose_read();
+        let array_name = rx.rename.read_name_or_ident(&field_name);
+        let select = rx.select.or(receiver.select).unwrap_or(true);
+        let check_units = rx.checked_units(receiver);
+
+        // `uom` quantities are lazily read as the numbers stored in the file, and converted in `load`
+        let lazy_type: syn::Type = syn::parse_quote!(<#stored_type as hdf5_derive::LazyTwin>::Lazy);
+
+        twin_fields = quote!(
+            #twin_fields
+            #(#field_docs)*
+            #field_vis #field_name: #lazy_type,
+        );
+
+        let load = rx.convert_stored(quote!(<#stored_type as hdf5_derive::LazyTwin>::from_lazy(self.#field_name, #transpose)?));
+
+        load_fields = quote!(
+            #load_fields
+            #field_name: #load,
+        );
+
+        // lazy types always read the data as it is stored on disk, transposing
+        // is applied in `load`
+        read_data.push(ReadInfo {
+            field_name,
+            field_type: lazy_type.clone(),
+            stored_type: lazy_type,
+            array_name,
+            transpose: false,
+            select,
+            from_stored: false,
+            check_units,
+        });
+    }
+
+    let read_impl = read_codegen(twin.clone(), span, &read_data, false)?;
+    let read_selection_impl = read_codegen(twin.clone(), span, &read_data, true)?;
+
+    let read_report_impl = read_report_codegen(twin.clone(), span, &read_data);
+    let validate_impl = validate_codegen(span, &read_data);
+
+    let check = write_complete_check(receiver);
+    let report_check = write_complete_report(receiver);
+    let validate_check = write_complete_validate(receiver);
+
+    let struct_doc = format!(
+        "Lazily read version of [`{}`], generated with `#[hdf5(lazy_twin)]`",
+        ident
+    );
+    let load_doc = format!(
+        "read the remaining data from the file to construct a [`{}`]",
+        ident
+    );
+
+    let output = quote!(
+        #[doc = #struct_doc]
+        #vis struct #twin #generics #wher {
+            #twin_fields
+        }
+
+        impl #imp hdf5_derive::ContainerRead for #twin #ty #wher {
+            fn read_hdf5(group: &hdf5_derive::Group) -> Result<Self, hdf5_derive::Error> {
+                #check
+                #read_impl
+            }
+
+            fn read_hdf5_selection(group: &hdf5_derive::Group, selection: hdf5_derive::Slice) -> Result<Self, hdf5_derive::Error> {
+                #check
+                #read_selection_impl
+            }
+
+            fn read_hdf5_report(group: &hdf5_derive::Group) -> Result<Self, hdf5_derive::Error> {
+                let mut errors: Vec<hdf5_derive::Error> = Vec::new();
+                #report_check
+                #read_report_impl
+            }
+
+            fn validate_hdf5(group: &hdf5_derive::Group) -> hdf5_derive::validate::ValidationReport {
+                let mut report = hdf5_derive::validate::ValidationReport::default();
+                #validate_check
+                #validate_impl
+                report
+            }
+        }
+
+        impl #imp #twin #ty #wher {
+            #[doc = #load_doc]
+            pub fn load(self) -> Result<#ident #ty, hdf5_derive::Error> {
+                Ok(#ident { #load_fields })
+            }
+        }
+    );
+
+    Ok(output)
+}
@@ -1,4 +1,7 @@
+mod attributes;
+mod lazy_twin;
 mod read;
+mod schema;
 mod write;
 
 use proc_macro::TokenStream;
@@ -28,6 +31,25 @@ pub fn container_write(input: TokenStream) -> TokenStream {
         .into()
 }
 
+#[proc_macro_derive(ContainerSchema, attributes(hdf5))]
+pub fn container_schema(input: TokenStream) -> TokenStream {
+    // Parse the input tokens into a syntax tree
+    let input = parse_macro_input!(input as DeriveInput);
+
+    schema::derive_container_schema(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(ContainerAttributes, attributes(hdf5))]
+pub fn container_attributes(input: TokenStream) -> TokenStream {
+    // Parse the input tokens into a syntax tree
+    let input = parse_macro_input!(input as DeriveInput);
+
+    attributes::derive_container_attributes(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
 #[derive(Debug, Clone, Copy, darling::FromMeta)]
 #[darling(default)]
@@ -74,16 +96,100 @@ impl Rename {
     }
 }
 
+#[derive(Debug, Clone, Default)]
+/// literal attributes declared with `attrs(name = "value", ..)`, in declaration order
+struct LiteralAttrs(Vec<(String, syn::Lit)>);
+
+impl darling::FromMeta for LiteralAttrs {
+    fn from_list(items: &[syn::NestedMeta]) -> darling::Result<Self> {
+        let mut attrs = Vec::new();
+
+        for item in items {
+            let syn::NestedMeta::Meta(syn::Meta::NameValue(name_value)) = item else {
+                return Err(darling::Error::custom("expected `name = literal`").with_span(item));
+            };
+
+            let name = name_value.path.get_ident().ok_or_else(|| {
+                darling::Error::custom("expected a plain attribute name")
+                    .with_span(&name_value.path)
+            })?;
+
+            match &name_value.lit {
+                syn::Lit::Str(_) | syn::Lit::Int(_) | syn::Lit::Float(_) => (),
+                lit => return Err(darling::Error::unexpected_lit_type(lit)),
+            }
+
+            attrs.push((name.to_string(), name_value.lit.clone()));
+        }
+
+        Ok(Self(attrs))
+    }
+}
+
+impl LiteralAttrs {
+    /// statements writing each attribute to a `location` variable in scope. Integers are written as `i64`
+    /// and floats as `f64`
+    fn write_statements(&self) -> Result<Vec<proc_macro2::TokenStream>> {
+        self.0
+            .iter()
+            .map(|(name, lit)| {
+                let statement = match lit {
+                    syn::Lit::Int(int) => {
+                        let value = proc_macro2::Literal::i64_suffixed(int.base10_parse()?);
+                        quote::quote!(hdf5_derive::metadata::write_scalar_attribute(location, #name, &#value)?;)
+                    }
+                    syn::Lit::Float(float) => {
+                        let value = proc_macro2::Literal::f64_suffixed(float.base10_parse()?);
+                        quote::quote!(hdf5_derive::metadata::write_scalar_attribute(location, #name, &#value)?;)
+                    }
+                    lit => quote::quote!(hdf5_derive::metadata::write_string_attribute(location, #name, #lit)?;),
+                };
+
+                Ok(statement)
+            })
+            .collect()
+    }
+
+    /// `hdf5_derive::schema::Attribute` expressions describing each attribute
+    fn schema_attributes(&self) -> Result<Vec<proc_macro2::TokenStream>> {
+        self.0
+            .iter()
+            .map(|(name, lit)| {
+                let value = match lit {
+                    syn::Lit::Int(int) => {
+                        let value = proc_macro2::Literal::i64_suffixed(int.base10_parse()?);
+                        quote::quote!(Integer(#value))
+                    }
+                    syn::Lit::Float(float) => {
+                        let value = proc_macro2::Literal::f64_suffixed(float.base10_parse()?);
+                        quote::quote!(Float(#value))
+                    }
+                    lit => quote::quote!(String(#lit)),
+                };
+
+                Ok(quote::quote!(
+                    hdf5_derive::schema::Attribute::literal(#name, hdf5_derive::schema::AttributeLiteral::#value)
+                ))
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, FromDeriveInput)]
-#[darling(supports(struct_any), attributes(hdf5))]
+#[darling(supports(struct_any), attributes(hdf5), forward_attrs(doc))]
 struct InputReceiver {
     /// The struct ident.
     #[allow(dead_code)]
     ident: syn::Ident,
 
+    /// doc comments on the struct
+    attrs: Vec<syn::Attribute>,
+
     #[allow(dead_code)]
     generics: syn::Generics,
 
+    vis: syn::Visibility,
+
     /// Receives the body of the struct or enum. We don't care about
     /// struct fields because we previously told darling we only accept structs.
     data: ast::Data<(), FieldReceiver>,
@@ -93,10 +199,32 @@ struct InputReceiver {
 
     #[darling(default)]
     mutate_on_write: bool,
+
+    #[darling(default)]
+    /// whether fields are restricted to the selection passed to `read_hdf5_selection` /
+    /// `write_hdf5_selection` by default
+    select: Option<bool>,
+
+    #[darling(default)]
+    /// name of a struct to generate that mirrors this one, with arrays
+    /// replaced by `LazyArray`s
+    lazy_twin: Option<String>,
+
+    #[darling(default)]
+    /// set a marker attribute after all fields are written, and require it when reading
+    write_complete: bool,
+
+    #[darling(default)]
+    /// write doc comments to a `description` attribute on the group and on every field
+    write_docs: bool,
+
+    #[darling(default)]
+    /// check the `units` attribute of every field with units when reading
+    check_units: bool,
 }
 
 #[derive(Debug, FromField, Clone)]
-#[darling(attributes(hdf5))]
+#[darling(attributes(hdf5), forward_attrs(doc))]
 struct FieldReceiver {
     /// Get the ident of the field. For fields in tuple or newtype structs or
     /// enum bodies, this can be `None`.
@@ -105,6 +233,11 @@ struct FieldReceiver {
     /// This magic field name pulls the type from the input.
     ty: syn::Type,
 
+    vis: syn::Visibility,
+
+    /// doc comments on the field
+    attrs: Vec<syn::Attribute>,
+
     #[darling(default)]
     /// whether or not to use `std::ops::Deref` on the field before 
     /// serializing the container
@@ -117,6 +250,104 @@ struct FieldReceiver {
 
     #[darling(default)]
     mutate_on_write: Option<bool>,
+
+    #[darling(default)]
+    /// whether or not to restrict this field to the selection passed to
+    /// `read_hdf5_selection` / `write_hdf5_selection`
+    select: Option<bool>,
+
+    #[darling(default)]
+    /// whether or not to write the doc comments of the field to a `description` attribute
+    write_docs: Option<bool>,
+
+    #[darling(default)]
+    /// units written to a `units` attribute
+    units: Option<String>,
+
+    #[darling(default)]
+    /// whether or not to check the `units` attribute when reading
+    check_units: Option<bool>,
+
+    #[darling(default)]
+    /// whether or not the field holds `uom` quantities, stored in SI base units
+    uom: bool,
+
+    #[darling(default, rename = "attrs")]
+    /// literal attributes written to the dataset or group of the field
+    literal_attrs: LiteralAttrs,
+}
+
+impl FieldReceiver {
+    /// the type stored in the file. `uom` quantities are stored as plain numbers
+    fn stored_type(&self) -> syn::Type {
+        let ty = &self.ty;
+
+        if self.uom {
+            syn::parse_quote!(<#ty as hdf5_derive::quantity::Quantities>::Stored)
+        } else {
+            ty.clone()
+        }
+    }
+
+    /// expression for the units of the field as a `&str`, if it has any
+    fn units(&self) -> Option<proc_macro2::TokenStream> {
+        let ty = &self.ty;
+
+        if self.uom {
+            Some(quote::quote!(&<#ty as hdf5_derive::quantity::Quantities>::units()))
+        } else {
+            self.units.as_ref().map(|units| quote::quote!(#units))
+        }
+    }
+
+    /// expression for the units to compare against the file when reading, if they are checked
+    fn checked_units(&self, receiver: &InputReceiver) -> Option<proc_macro2::TokenStream> {
+        if self.check_units.unwrap_or(receiver.check_units) {
+            self.units()
+        } else {
+            None
+        }
+    }
+
+    /// expression for a reference to the value of the field as it is stored in the file, with `self` in scope
+    fn stored_value(&self) -> proc_macro2::TokenStream {
+        let ty = &self.ty;
+        let field_name = self.ident.as_ref().unwrap();
+
+        if self.uom {
+            quote::quote!(&<#ty as hdf5_derive::quantity::Quantities>::to_stored(&self.#field_name))
+        } else {
+            quote::quote!(&self.#field_name)
+        }
+    }
+
+    /// convert `value`, of the stored type, to the type of the field
+    fn convert_stored(&self, value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let ty = &self.ty;
+
+        if self.uom {
+            quote::quote!(<#ty as hdf5_derive::quantity::Quantities>::from_stored(#value))
+        } else {
+            value
+        }
+    }
+}
+
+/// the text of the `///` doc comments in `attrs`, with the space after each `///` removed
+fn doc_string(attrs: &[syn::Attribute]) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Str(line),
+                ..
+            })) => Some(line.value()),
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').map(Into::into).unwrap_or(line))
+        .collect();
+
+    lines.join("\n").trim().to_string()
 }
 
 fn fields_from_input(input: &DeriveInput) -> Result<(InputReceiver, Vec<FieldReceiver>)> {
@@ -141,5 +372,53 @@ fn fields_from_input(input: &DeriveInput) -> Result<(InputReceiver, Vec<FieldRec
         }
     }?;
 
+    for field in &fields {
+        let ident = field.ident.as_ref().unwrap();
+        let names = [
+            field.rename.read_name_or_ident(ident),
+            field.rename.write_name_or_ident(ident),
+        ];
+
+        if receiver.write_complete && names.iter().any(|name| name == "write_complete") {
+            return Err(syn::Error::new(
+                ident.span(),
+                "`write_complete` is the name of the marker attribute of `#[hdf5(write_complete)]` containers, rename this field",
+            ));
+        }
+
+        // scalar fields store their units and docs next to them, in `<name>_units` and `<name>_description`
+        // attributes
+        let has_units = field.uom || field.units.is_some();
+        let has_docs =
+            field.write_docs.unwrap_or(receiver.write_docs) && !doc_string(&field.attrs).is_empty();
+
+        for (suffix, used) in [("units", has_units), ("description", has_docs)] {
+            if !used {
+                continue;
+            }
+
+            let sibling_name = format!("{}_{suffix}", field.rename.write_name_or_ident(ident));
+
+            let collision = fields.iter().find(|other| {
+                let other_ident = other.ident.as_ref().unwrap();
+                other.rename.write_name_or_ident(other_ident) == sibling_name
+            });
+
+            if let Some(other) = collision {
+                return Err(syn::Error::new(
+                    other.ident.span(),
+                    format!("`{sibling_name}` is where the {suffix} of `{ident}` is written if it is a scalar, rename this field"),
+                ));
+            }
+        }
+
+        if field.uom && field.units.is_some() {
+            return Err(syn::Error::new(
+                field.ident.span(),
+                "`uom` fields are stored in SI base units, and cannot have different `units`",
+            ));
+        }
+    }
+
     Ok((receiver, fields))
 }
@@ -5,27 +5,79 @@ use syn::spanned::Spanned;
 use quote::quote;
 use proc_macro2::Span;
 use syn::punctuated::Punctuated;
-use super::{fields_from_input, FieldReceiver};
+use super::{fields_from_input, FieldReceiver, InputReceiver};
+use super::lazy_twin::lazy_twin_codegen;
 
 pub(crate) struct ReadInfo {
     pub(crate) field_name: syn::Ident,
     pub(crate) field_type: syn::Type,
+    /// the type read from the file, which differs from `field_type` for `uom` quantities
+    pub(crate) stored_type: syn::Type,
     pub(crate) array_name: String,
     pub(crate) transpose: bool,
+    pub(crate) select: bool,
+    /// whether the stored value is converted to `field_type` with `Quantities::from_stored`
+    pub(crate) from_stored: bool,
+    /// expression for the units checked before reading, if enabled
+    pub(crate) check_units: Option<TokenStream>,
 }
 
-pub(crate) fn read_codegen(ident: syn::Ident, span: Span, arrays: &[ReadInfo]) -> Result<TokenStream> {
+impl ReadInfo {
+    /// wrap `read`, reading the stored type, with the units check and the conversion to the field type
+    fn finish_read(&self, name: &syn::LitStr, read: TokenStream) -> TokenStream {
+        let field_type = &self.field_type;
+
+        let read = if self.from_stored {
+            quote!(#read.map(<#field_type as hdf5_derive::quantity::Quantities>::from_stored))
+        } else {
+            read
+        };
+
+        match &self.check_units {
+            Some(units) => quote!({
+                hdf5_derive::metadata::check_units(group, #name, #units)?;
+                #read
+            }),
+            None => read,
+        }
+    }
+}
+
+/// generate the body of a read method. If `with_selection` is set, fields with `select` enabled
+/// are restricted to a `selection` variable in scope
+pub(crate) fn read_codegen(
+    ident: syn::Ident,
+    span: Span,
+    arrays: &[ReadInfo],
+    with_selection: bool,
+) -> Result<TokenStream> {
     let mut body = quote!();
 
 
     for array_or_attribute in arrays {
-        let ReadInfo { field_name, field_type, array_name, transpose } = array_or_attribute;
+        let ReadInfo {
+            field_name,
+            field_type,
+            stored_type,
+            array_name,
+            transpose,
+            select,
+            ..
+        } = array_or_attribute;
 
         let name = syn::LitStr::new(&array_name, span);
+        let rust_name = syn::LitStr::new(&field_name.to_string(), span);
+
+        let read = if with_selection && *select {
+            quote!(<#stored_type as hdf5_derive::ReadGroup>::read_group_selection(group, #name, #transpose, selection))
+        } else {
+            quote!(<#stored_type as hdf5_derive::ReadGroup>::read_group(group, #name, #transpose))
+        };
+        let read = array_or_attribute.finish_read(&name, read);
 
         body = quote!(
             #body
-            let #field_name : #field_type = hdf5_derive::ReadGroup::read_group(group, #name, #transpose)?;
+            let #field_name : #field_type = hdf5_derive::progress::field(group, #name, #rust_name, hdf5_derive::progress::Operation::Read, || #read)?;
         );
     }
 
@@ -43,6 +95,96 @@ pub(crate) fn read_codegen(ident: syn::Ident, span: Span, arrays: &[ReadInfo]) -
     Ok(full_impl)
 }
 
+/// generate the body of a `read_hdf5_report` method, which reads every field before returning
+/// the errors of all of them. The `errors` variable must be in scope
+pub(crate) fn read_report_codegen(
+    ident: syn::Ident,
+    span: Span,
+    arrays: &[ReadInfo],
+) -> TokenStream {
+    let mut body = quote!();
+
+    for array_or_attribute in arrays {
+        let ReadInfo {
+            field_name,
+            field_type,
+            stored_type,
+            array_name,
+            transpose,
+            ..
+        } = array_or_attribute;
+
+        let name = syn::LitStr::new(array_name, span);
+        let rust_name = syn::LitStr::new(&field_name.to_string(), span);
+
+        let read = quote!(<#stored_type as hdf5_derive::ReadGroup>::read_group_report(group, #name, #transpose));
+        let read = array_or_attribute.finish_read(&name, read);
+
+        body = quote!(
+            #body
+            let #field_name : Option<#field_type> = hdf5_derive::progress::report_field(&mut errors, group, #name, #rust_name, || #read)?;
+        );
+    }
+
+    // every field is `Some` once there are no errors
+    let punct: Punctuated<TokenStream, syn::Token![,]> = arrays
+        .iter()
+        .map(|arr| {
+            let field_name = &arr.field_name;
+            quote!(#field_name: #field_name.expect("fields are read when there are no errors"))
+        })
+        .collect();
+
+    quote!(
+        #body
+
+        if !errors.is_empty() {
+            return Err(hdf5_derive::error::Aggregate::new(errors).into());
+        }
+
+        Ok(#ident { #punct })
+    )
+}
+
+/// generate the body of a `validate_hdf5` method, checking every field without reading it. The
+/// `report` variable must be in scope
+pub(crate) fn validate_codegen(span: Span, arrays: &[ReadInfo]) -> TokenStream {
+    let mut body = quote!();
+
+    for array_or_attribute in arrays {
+        let ReadInfo {
+            field_name,
+            stored_type,
+            array_name,
+            transpose,
+            check_units,
+            ..
+        } = array_or_attribute;
+
+        let name = syn::LitStr::new(array_name, span);
+        let rust_name = syn::LitStr::new(&field_name.to_string(), span);
+
+        let units = match check_units {
+            Some(units) => quote!(
+                if let Err(e) = hdf5_derive::metadata::check_units(group, #name, #units) {
+                    report.push_problem(e);
+                }
+            ),
+            None => quote!(),
+        };
+
+        body = quote!(
+            #body
+            hdf5_derive::validate::field(&mut report, group, #name, #rust_name, |report| {
+                <#stored_type as hdf5_derive::ReadGroup>::validate_group(group, #name, #transpose, report);
+                #units
+            });
+        );
+    }
+
+    body
+}
+
 pub(crate) fn derive_container_read(input: DeriveInput) -> Result<TokenStream> {
     let (receiver, fields_information) = fields_from_input(&input)?;
 
@@ -52,27 +194,108 @@ pub(crate) fn derive_container_read(input: DeriveInput) -> Result<TokenStream> {
         .map(|rx: &FieldReceiver| {
             let field_name = rx.ident.clone().unwrap();
             let field_type = rx.ty.clone();
+            let stored_type = rx.stored_type();
             let transpose = rx.transpose.unwrap_or(receiver.transpose).transpose_read();
 
             let array_name = rx.rename.read_name_or_ident(&field_name);
+            let select = rx.select.or(receiver.select).unwrap_or(true);
+            let check_units = rx.checked_units(&receiver);
 
-            ReadInfo {field_name, field_type, transpose, array_name }
-
-        }).collect();
-
+            ReadInfo {
+                field_name,
+                field_type,
+                stored_type,
+                transpose,
+                array_name,
+                select,
+                from_stored: rx.uom,
+                check_units,
+            }
+        })
+        .collect();
 
-    let read_impl = read_codegen(receiver.ident.clone(), input.span(), &read_data)?;
+    let read_impl = read_codegen(receiver.ident.clone(), input.span(), &read_data, false)?;
+    let read_selection_impl = read_codegen(receiver.ident.clone(), input.span(), &read_data, true)?;
 
     let (imp, ty, wher) = receiver.generics.split_for_impl();
     let ident = receiver.ident.clone();
 
+    let lazy_twin = match &receiver.lazy_twin {
+        Some(twin_name) => {
+            lazy_twin_codegen(&receiver, &fields_information, twin_name, input.span())?
+        }
+        None => quote!(),
+    };
+
+    let read_report_impl = read_report_codegen(receiver.ident.clone(), input.span(), &read_data);
+    let validate_impl = validate_codegen(input.span(), &read_data);
+
+    let check = write_complete_check(&receiver);
+    let report_check = write_complete_report(&receiver);
+    let validate_check = write_complete_validate(&receiver);
+
     let output = quote::quote!(
         impl #imp hdf5_derive::ContainerRead for #ident #ty #wher {
             fn read_hdf5(group: &hdf5_derive::Group) -> Result<Self, hdf5_derive::Error> {
+                #check
                 #read_impl
             }
+
+            fn read_hdf5_selection(group: &hdf5_derive::Group, selection: hdf5_derive::Slice) -> Result<Self, hdf5_derive::Error> {
+                #check
+                #read_selection_impl
+            }
+
+            fn read_hdf5_report(group: &hdf5_derive::Group) -> Result<Self, hdf5_derive::Error> {
+                let mut errors: Vec<hdf5_derive::Error> = Vec::new();
+                #report_check
+                #read_report_impl
+            }
+
+            fn validate_hdf5(group: &hdf5_derive::Group) -> hdf5_derive::validate::ValidationReport {
+                let mut report = hdf5_derive::validate::ValidationReport::default();
+                #validate_check
+                #validate_impl
+                report
+            }
         }
+
+        #lazy_twin
     ).into();
 
     Ok(output)
 }
+
+/// statement checking the `write_complete` marker of the group before reading any fields,
+/// if the container requires it
+pub(crate) fn write_complete_check(receiver: &InputReceiver) -> TokenStream {
+    if receiver.write_complete {
+        quote!(hdf5_derive::write_complete::check(group)?;)
+    } else {
+        quote!()
+    }
+}
+
+/// statement adding an error to `errors` if the `write_complete` marker of the group is not set,
+/// if the container requires it
+pub(crate) fn write_complete_report(receiver: &InputReceiver) -> TokenStream {
+    if receiver.write_complete {
+        quote!(if let Err(e) = hdf5_derive::write_complete::check(group) {
+            errors.push(e);
+        })
+    } else {
+        quote!()
+    }
+}
+
+/// statement recording a problem in `report` if the `write_complete` marker of the group is not
+/// set, if the container requires it
+pub(crate) fn write_complete_validate(receiver: &InputReceiver) -> TokenStream {
+    if receiver.write_complete {
+        quote!(if let Err(e) = hdf5_derive::write_complete::check(group) {
+            report.push_problem(e);
+        })
+    } else {
+        quote!()
+    }
+}
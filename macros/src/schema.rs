@@ -0,0 +1,74 @@
+use super::{doc_string, fields_from_input, FieldReceiver};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{DeriveInput, Result};
+
+pub(crate) fn derive_container_schema(input: DeriveInput) -> Result<TokenStream> {
+    let (receiver, fields_information) = fields_from_input(&input)?;
+    let span = input.span();
+
+    let fields = fields_information
+        .iter()
+        .map(|rx: &FieldReceiver| {
+            let field_name = rx.ident.clone().unwrap();
+            let stored_type = rx.stored_type();
+            let transpose = rx.transpose.unwrap_or(receiver.transpose);
+
+            let rust_name = syn::LitStr::new(&field_name.to_string(), span);
+            let read_name = syn::LitStr::new(&rx.rename.read_name_or_ident(&field_name), span);
+            let write_name = syn::LitStr::new(&rx.rename.write_name_or_ident(&field_name), span);
+
+            let transpose_read = transpose.transpose_read();
+            let transpose_write = transpose.transpose_write();
+            let mutate_on_write = rx.mutate_on_write.unwrap_or(receiver.mutate_on_write);
+            let select = rx.select.or(receiver.select).unwrap_or(true);
+            let docs = doc_string(&rx.attrs);
+            let units = match rx.units() {
+                Some(units) => quote!(Some(String::from(#units))),
+                None => quote!(None),
+            };
+            let attributes = rx.literal_attrs.schema_attributes()?;
+
+            Ok(quote!(
+                hdf5_derive::schema::Field {
+                    rust_name: #rust_name,
+                    read_name: #read_name,
+                    write_name: #write_name,
+                    docs: #docs,
+                    layout: {
+                        let mut layout = <#stored_type as hdf5_derive::schema::FieldLayout>::layout();
+                        layout.attributes.extend([#(#attributes),*]);
+                        layout
+                    },
+                    units: #units,
+                    transpose_read: #transpose_read,
+                    transpose_write: #transpose_write,
+                    mutate_on_write: #mutate_on_write,
+                    select: #select,
+                }
+            ))
+        })
+        .collect::<Result<Vec<TokenStream>>>()?;
+
+    let (imp, ty, wher) = receiver.generics.split_for_impl();
+    let ident = receiver.ident.clone();
+    let name = syn::LitStr::new(&ident.to_string(), span);
+    let write_complete = receiver.write_complete;
+    let docs = doc_string(&receiver.attrs);
+
+    let output = quote!(
+        impl #imp hdf5_derive::ContainerSchema for #ident #ty #wher {
+            fn schema() -> hdf5_derive::schema::Schema {
+                hdf5_derive::schema::Schema {
+                    name: #name,
+                    docs: #docs,
+                    write_complete: #write_complete,
+                    fields: vec![#(#fields),*],
+                }
+            }
+        }
+    );
+
+    Ok(output)
+}
@@ -3,38 +3,122 @@ use syn::{DeriveInput, Result};
 use syn::spanned::Spanned;
 use quote::quote;
 use proc_macro2::Span;
-use super::{fields_from_input, FieldReceiver};
+use super::{doc_string, fields_from_input, FieldReceiver};
 
 pub(crate) struct WriteInfo {
     pub(crate) field_name: syn::Ident,
+    /// reference to the value written to the file
+    pub(crate) value: TokenStream,
+    /// the type of `value`
+    pub(crate) stored_type: syn::Type,
     pub(crate) array_name: String,
     pub(crate) transpose: bool,
     pub(crate) mutate_on_write: bool,
+    pub(crate) select: bool,
+    /// doc comments to write to the `description` attribute, if enabled
+    pub(crate) docs: Option<String>,
+    /// expression for the units to write to the `units` attribute, if any
+    pub(crate) units: Option<TokenStream>,
+    /// statements writing the literal `attrs(..)` to a `location` variable
+    pub(crate) literal_attrs: Vec<TokenStream>,
 }
 
-pub(crate) fn write_codegen(span: Span, arrays: &[WriteInfo]) -> Result<TokenStream> {
+/// generate the statements writing each field in a write method. If `with_selection` is set, fields
+/// with `select` enabled are written at an `offset` variable in scope
+pub(crate) fn write_codegen(
+    span: Span,
+    arrays: &[WriteInfo],
+    with_selection: bool,
+) -> Result<TokenStream> {
     let mut body = quote!();
 
     for array_or_attribute in arrays {
-        let WriteInfo { field_name, array_name, transpose, mutate_on_write } = array_or_attribute;
+        let WriteInfo {
+            field_name,
+            value,
+            stored_type,
+            array_name,
+            transpose,
+            mutate_on_write,
+            select,
+            docs,
+            units,
+            literal_attrs,
+        } = array_or_attribute;
 
         let name = syn::LitStr::new(&array_name, span);
+        let rust_name = syn::LitStr::new(&field_name.to_string(), span);
+
+        let write = if with_selection && *select {
+            quote!(hdf5_derive::WriteGroup::write_group_selection(#value, &file, #name, #transpose, #mutate_on_write, offset))
+        } else {
+            quote!(hdf5_derive::WriteGroup::write_group(#value, &file, #name, #transpose, #mutate_on_write))
+        };
+
+        // attributes describing the field are written once the field exists
+        let mut metadata = Vec::new();
+        if let Some(docs) = docs {
+            metadata.push(quote!(hdf5_derive::metadata::describe(file, #name, #docs)?;));
+        }
+        if let Some(units) = units {
+            metadata.push(quote!(hdf5_derive::metadata::write_units(file, #name, #units)?;));
+        }
+        if !literal_attrs.is_empty() {
+            metadata.push(quote!(
+                hdf5_derive::metadata::attach::<#stored_type, _>(file, #name, |location| {
+                    #(#literal_attrs)*
+                    Ok(())
+                })?;
+            ));
+        }
+
+        let write = if metadata.is_empty() {
+            write
+        } else {
+            quote!({
+                #write?;
+                #(#metadata)*
+                Ok(())
+            })
+        };
 
         body = quote!(
             #body
-            
-            hdf5_derive::WriteGroup::write_group(&self.#field_name, &file, #name, #transpose, #mutate_on_write)?;
+
+            hdf5_derive::progress::field(file, #name, #rust_name, hdf5_derive::progress::Operation::Write, || #write)?;
         );
     }
 
-    // generate the full method implementation
-    let full_impl = quote!(
-        #body
+    Ok(body)
+}
 
-        Ok(())
-    );
+/// generate the statements appending each field with `select` enabled to the end of its dataset.
+/// Other fields and the attributes describing fields are not written
+pub(crate) fn append_codegen(span: Span, arrays: &[WriteInfo]) -> TokenStream {
+    let mut body = quote!();
 
-    Ok(full_impl)
+    for array_or_attribute in arrays.iter().filter(|info| info.select) {
+        let WriteInfo {
+            field_name,
+            value,
+            array_name,
+            transpose,
+            ..
+        } = array_or_attribute;
+
+        let name = syn::LitStr::new(array_name, span);
+        let rust_name = syn::LitStr::new(&field_name.to_string(), span);
+
+        body = quote!(
+            #body
+
+            hdf5_derive::progress::field(file, #name, #rust_name, hdf5_derive::progress::Operation::Write, || {
+                hdf5_derive::WriteGroup::append_group(#value, file, #name, #transpose)
+            })?;
+        );
+    }
+
+    body
 }
 
 pub(crate) fn derive_container_write(input: DeriveInput) -> Result<TokenStream> {
@@ -49,22 +133,83 @@ pub(crate) fn derive_container_write(input: DeriveInput) -> Result<TokenStream>
             let array_name = rx.rename.write_name_or_ident(&field_name);
 
             let mutate_on_write = rx.mutate_on_write.unwrap_or(receiver.mutate_on_write);
-
-            WriteInfo {field_name, transpose, array_name, mutate_on_write}
-
-        }).collect();
+            let select = rx.select.or(receiver.select).unwrap_or(true);
+
+            // fields without doc comments are left without a description
+            let docs = Some(doc_string(&rx.attrs))
+                .filter(|docs| rx.write_docs.unwrap_or(receiver.write_docs) && !docs.is_empty());
+
+            let value = rx.stored_value();
+            let stored_type = rx.stored_type();
+            let units = rx.units();
+            let literal_attrs = rx.literal_attrs.write_statements()?;
+
+            Ok(WriteInfo {
+                field_name,
+                value,
+                stored_type,
+                transpose,
+                array_name,
+                mutate_on_write,
+                select,
+                docs,
+                units,
+                literal_attrs,
+            })
+        })
+        .collect::<Result<_>>()?;
 
     //Ok(combine_impls(receiver.ident, receiver.generics, read_impl, write_impl).into());
 
-    let write_impl = write_codegen(input.span(), &write_data)?;
+    let write_impl = write_codegen(input.span(), &write_data, false)?;
+    let write_selection_impl = write_codegen(input.span(), &write_data, true)?;
+    let append_impl = append_codegen(input.span(), &write_data);
 
     let (imp, ty, wher) = receiver.generics.split_for_impl();
     let ident = receiver.ident.clone();
 
+    // the marker is cleared before any field is written, and only set once all of them succeed. A
+    // selection is a single block of the container, so the caller manages the marker around all blocks
+    let (begin, end) = if receiver.write_complete {
+        (
+            quote!(hdf5_derive::write_complete::clear(file)?;),
+            quote!(hdf5_derive::write_complete::mark(file)?;),
+        )
+    } else {
+        (quote!(), quote!())
+    };
+
+    // the group of the container is described before its fields are written, so that the docs of a
+    // field holding the container replace the docs of the struct
+    let struct_docs = doc_string(&receiver.attrs);
+    let describe = if receiver.write_docs && !struct_docs.is_empty() {
+        quote!(hdf5_derive::metadata::write_string_attribute(file, hdf5_derive::metadata::DESCRIPTION, #struct_docs)?;)
+    } else {
+        quote!()
+    };
+
     let output = quote::quote!(
         impl #imp hdf5_derive::ContainerWrite for #ident #ty #wher {
             fn write_hdf5(&self, file: &hdf5_derive::Group) -> Result<(), hdf5_derive::Error> {
+                #begin
+                #describe
                 #write_impl
+                #end
+
+                Ok(())
+            }
+
+            fn write_hdf5_selection(&self, file: &hdf5_derive::Group, offset: usize) -> Result<(), hdf5_derive::Error> {
+                #describe
+                #write_selection_impl
+
+                Ok(())
+            }
+
+            fn append_hdf5(&self, file: &hdf5_derive::Group) -> Result<(), hdf5_derive::Error> {
+                #append_impl
+
+                Ok(())
             }
         }
     ).into();
@@ -0,0 +1,171 @@
+//! Async wrappers around derived reads and writes, for use from a tokio runtime
+//!
+//! Requires the `async` feature. (The module is called `async_io` since `async` is a keyword.)
+//!
+//! HDF5 calls block, and the `hdf5` crate serializes every call behind a single global lock, so
+//! they must not run on the threads of an async executor. Each function here moves its work to
+//! the tokio blocking thread pool with [`tokio::task::spawn_blocking`], and waits for it without
+//! blocking the executor. Blocking threads still take turns on the global lock, so running many
+//! reads at once does not make them faster, but it does keep the rest of the service responsive.
+//!
+//! Dropping one of these futures before it completes cancels the work at the next field of the
+//! container (see [`crate::CancellationToken`]). The field being read or written when the future is
+//! dropped is finished first, since HDF5 calls cannot be interrupted.
+//!
+//! [`crate::LazyArray::slice_async`] and [`crate::LazyArray::write_slice_async`] do the same for
+//! slices of a single dataset.
+//!
+//! ```
+//! use hdf5_derive::{ContainerRead, ContainerWrite, WriteMode};
+//! use hdf5_derive::async_io::{read_hdf5_from_path_async, write_hdf5_to_path_async};
+//! use ndarray::Array2;
+//!
+//! #[derive(ContainerRead, ContainerWrite)]
+//! struct Data {
+//!     pressure: Array2<f64>,
+//! }
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! let path = "./async_io_doc.h5";
+//!
+//! runtime.block_on(async {
+//!     let data = Data { pressure: Array2::ones((4, 4)) };
+//!     write_hdf5_to_path_async(data, path, WriteMode::Truncate).await.unwrap();
+//!
+//!     let read: Data = read_hdf5_from_path_async(path).await.unwrap();
+//!     assert_eq!(read.pressure, Array2::ones((4, 4)));
+//! });
+//!
+//! std::fs::remove_file(path).unwrap();
+//! ```
+
+use std::path::PathBuf;
+
+use hdf5::Group;
+
+use crate::error;
+use crate::CancellationToken;
+use crate::ContainerRead;
+use crate::ContainerWrite;
+use crate::Error;
+use crate::WriteMode;
+
+/// read a container from `group` on the blocking thread pool. See [`ContainerRead::read_hdf5`]
+pub async fn read_hdf5_async<T>(group: Group) -> Result<T, Error>
+where
+    T: ContainerRead + Send + 'static,
+{
+    blocking(move || T::read_hdf5(&group)).await
+}
+
+/// write `container` to `group` on the blocking thread pool. See [`ContainerWrite::write_hdf5`]
+pub async fn write_hdf5_async<T>(container: T, group: Group) -> Result<(), Error>
+where
+    T: ContainerWrite + Send + 'static,
+{
+    blocking(move || container.write_hdf5(&group)).await
+}
+
+/// open the file at `path` and read a container from its root group on the blocking thread pool.
+/// See [`ContainerRead::read_hdf5_from_path`]
+pub async fn read_hdf5_from_path_async<T, P>(path: P) -> Result<T, Error>
+where
+    T: ContainerRead + Send + 'static,
+    P: Into<PathBuf>,
+{
+    let path = path.into();
+    blocking(move || T::read_hdf5_from_path(&path)).await
+}
+
+/// write `container` to the root group of the file at `path` on the blocking thread pool.
+/// See [`ContainerWrite::write_hdf5_to_path`]
+pub async fn write_hdf5_to_path_async<T, P>(
+    container: T,
+    path: P,
+    mode: WriteMode,
+) -> Result<(), Error>
+where
+    T: ContainerWrite + Send + 'static,
+    P: Into<PathBuf>,
+{
+    let path = path.into();
+    blocking(move || container.write_hdf5_to_path(&path, mode)).await
+}
+
+/// run `f` on the blocking thread pool, cancelling it (between fields) if the returned future is
+/// dropped before `f` finishes
+pub(crate) async fn blocking<R, F>(f: F) -> Result<R, Error>
+where
+    F: FnOnce() -> Result<R, Error> + Send + 'static,
+    R: Send + 'static,
+{
+    let token = CancellationToken::new();
+    let _cancel_on_drop = CancelOnDrop(token.clone());
+
+    match tokio::task::spawn_blocking(move || token.run(f)).await {
+        Ok(result) => result,
+        Err(e) => Err(error::BlockingTask::new(&e.to_string()).into()),
+    }
+}
+
+/// cancels the token when the future holding it is dropped. Cancelling after the task has
+/// finished has no effect
+struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::{s, Array1, Array2};
+
+    #[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+    struct Data {
+        arr: Array2<f64>,
+        value: u32,
+    }
+
+    #[tokio::test]
+    async fn group_roundtrip() {
+        let file = crate::memory::create_in_memory().unwrap();
+        let arr = Array2::from_shape_fn((4, 3), |(i, j)| (i * 3 + j) as f64);
+
+        let data = Data {
+            arr: arr.clone(),
+            value: 5,
+        };
+        write_hdf5_async(data, file.as_group().unwrap())
+            .await
+            .unwrap();
+        let read: Data = read_hdf5_async(file.as_group().unwrap()).await.unwrap();
+
+        assert_eq!(read, Data { arr, value: 5 });
+    }
+
+    #[tokio::test]
+    async fn lazy_slices() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        let data = Data {
+            arr: Array2::ones((4, 3)),
+            value: 5,
+        };
+        data.write_hdf5(&file).unwrap();
+
+        let lazy = crate::LazyArray2::<f64>::new(file.dataset("arr").unwrap()).unwrap();
+
+        lazy.write_slice_async(Array1::zeros(3), s![1, ..])
+            .await
+            .unwrap();
+        let row = lazy.slice_async(s![1, ..]).await.unwrap();
+
+        assert_eq!(row, Array1::<f64>::zeros(3));
+    }
+}
@@ -0,0 +1,282 @@
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+use hdf5::Group;
+
+use crate::error;
+use crate::ContainerWrite;
+use crate::Error;
+use crate::WriteMode;
+
+/// Writes owned containers on a dedicated background thread, so that the I/O of large
+/// writes overlaps with computation.
+///
+/// Containers are queued with [`AsyncWriter::submit`] (to a path) or [`AsyncWriter::submit_group`]
+/// (to a group of an open file), and written in the order they were submitted. The queue holds at
+/// most `capacity` containers: when it is full, `submit` blocks until the writer thread has
+/// finished the oldest write, which bounds the memory held by pending writes.
+///
+/// Errors from background writes are stored, and returned by the next call to
+/// [`AsyncWriter::flush`] or [`AsyncWriter::join`]. A failed write does not stop later writes.
+///
+/// ```
+/// use hdf5_derive::{AsyncWriter, ContainerRead, ContainerWrite, WriteMode};
+/// use ndarray::Array3;
+///
+/// #[derive(ContainerRead, ContainerWrite)]
+/// struct Snapshot {
+///     velocity: Array3<f64>,
+///     step: u64,
+/// }
+///
+/// let writer = AsyncWriter::new(2);
+///
+/// for step in 0..4 {
+///     // ... compute the next snapshot while the previous one is written
+///     let snapshot = Snapshot { velocity: Array3::zeros((8, 8, 8)), step };
+///
+///     writer
+///         .submit(format!("./async_writer_doc_{step}.h5"), WriteMode::Truncate, snapshot)
+///         .unwrap();
+/// }
+///
+/// // wait for all writes to finish, and check that they succeeded
+/// writer.join().unwrap();
+///
+/// let last = Snapshot::read_hdf5_from_path("./async_writer_doc_3.h5").unwrap();
+/// assert_eq!(last.step, 3);
+///
+/// for step in 0..4 {
+///     std::fs::remove_file(format!("./async_writer_doc_{step}.h5")).unwrap();
+/// }
+/// ```
+pub struct AsyncWriter<T> {
+    sender: Option<mpsc::SyncSender<Job<T>>>,
+    handle: Option<JoinHandle<Option<Error>>>,
+}
+
+/// a unit of work for the writer thread
+enum Job<T> {
+    Write(Target, T),
+    /// reply with the first error since the last flush, once every earlier job is done
+    Flush(mpsc::Sender<Option<Error>>),
+}
+
+/// where a queued container is written
+enum Target {
+    Path(PathBuf, WriteMode),
+    Group(Group),
+}
+
+impl<T> AsyncWriter<T>
+where
+    T: ContainerWrite + Send + 'static,
+{
+    /// start the writer thread, with a queue of at most `capacity` pending containers
+    ///
+    /// With a `capacity` of `0`, `submit` blocks until the writer thread starts writing
+    /// the container.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+
+        let handle = std::thread::Builder::new()
+            .name("hdf5-derive-writer".into())
+            .spawn(move || run(receiver))
+            .expect("failed to spawn the background writer thread");
+
+        Self {
+            sender: Some(sender),
+            handle: Some(handle),
+        }
+    }
+
+    /// queue `container` to be written to the file at `path`, opened according to `mode`
+    ///
+    /// Blocks while the queue is full.
+    pub fn submit<P: Into<PathBuf>>(
+        &self,
+        path: P,
+        mode: WriteMode,
+        container: T,
+    ) -> Result<(), Error> {
+        self.send(Job::Write(Target::Path(path.into(), mode), container))
+    }
+
+    /// queue `container` to be written to `group` with [`ContainerWrite::write_hdf5`]
+    ///
+    /// Blocks while the queue is full. The file holding `group` stays open until the write
+    /// has finished.
+    pub fn submit_group(&self, group: Group, container: T) -> Result<(), Error> {
+        self.send(Job::Write(Target::Group(group), container))
+    }
+
+    /// wait for every container submitted so far to be written
+    ///
+    /// Returns the first error from the writes since the previous flush, if any.
+    pub fn flush(&self) -> Result<(), Error> {
+        let (reply, response) = mpsc::channel();
+        self.send(Job::Flush(reply))?;
+
+        match response.recv() {
+            Ok(None) => Ok(()),
+            Ok(Some(e)) => Err(e),
+            Err(_) => Err(error::BackgroundWriter::new(
+                "the writer thread stopped before flushing",
+            )
+            .into()),
+        }
+    }
+
+    /// write every pending container, then stop the writer thread
+    ///
+    /// Returns the first error from the writes since the previous flush, if any.
+    pub fn join(mut self) -> Result<(), Error> {
+        self.shutdown()
+    }
+
+    fn send(&self, job: Job<T>) -> Result<(), Error> {
+        self.sender
+            .as_ref()
+            .expect("the sender is only taken on shutdown")
+            .send(job)
+            .map_err(|_| error::BackgroundWriter::new("the writer thread has stopped").into())
+    }
+
+    fn shutdown(&mut self) -> Result<(), Error> {
+        // closing the queue stops the writer thread once it is empty
+        self.sender.take();
+
+        let handle = match self.handle.take() {
+            Some(handle) => handle,
+            None => return Ok(()),
+        };
+
+        match handle.join() {
+            Ok(None) => Ok(()),
+            Ok(Some(e)) => Err(e),
+            Err(_) => Err(error::BackgroundWriter::new("the writer thread panicked").into()),
+        }
+    }
+}
+
+impl<T> Drop for AsyncWriter<T> {
+    /// writes every pending container before returning. Errors are discarded, use
+    /// [`AsyncWriter::join`] to check them
+    fn drop(&mut self) {
+        self.sender.take();
+
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+/// body of the writer thread: write jobs in order until the queue is closed, returning
+/// the first error not yet reported by a flush
+fn run<T: ContainerWrite>(receiver: mpsc::Receiver<Job<T>>) -> Option<Error> {
+    let mut first_error = None;
+
+    for job in receiver {
+        match job {
+            Job::Write(target, container) => {
+                let result = match target {
+                    Target::Path(path, mode) => container.write_hdf5_to_path(&path, mode),
+                    Target::Group(group) => container.write_hdf5(&group),
+                };
+
+                // the container is dropped here, freeing its memory before the next write
+                if let Err(e) = result {
+                    first_error.get_or_insert(e);
+                }
+            }
+            Job::Flush(reply) => {
+                reply.send(first_error.take()).ok();
+            }
+        }
+    }
+
+    first_error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContainerRead;
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::Array1;
+
+    #[derive(ContainerRead, ContainerWrite, Debug, PartialEq, Clone)]
+    struct Data {
+        arr: Array1<u64>,
+        step: u64,
+    }
+
+    #[test]
+    fn writes_in_order() {
+        let path = "./async_writer_in_order.h5";
+        let writer = AsyncWriter::new(1);
+
+        // every write replaces the file, so only the last one is visible
+        for step in 0..8 {
+            let data = Data {
+                arr: Array1::from_elem(16, step),
+                step,
+            };
+            writer.submit(path, WriteMode::Truncate, data).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let last = Data::read_hdf5_from_path(path).unwrap();
+        assert_eq!(last.arr, Array1::from_elem(16, 7));
+        assert_eq!(last.step, 7);
+
+        writer.join().unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn errors_reported_on_flush() {
+        let path = "./async_writer_errors.h5";
+        std::fs::remove_file(path).ok();
+
+        let writer = AsyncWriter::new(4);
+
+        let created = Data {
+            arr: Array1::from_elem(16, 1),
+            step: 1,
+        };
+        writer
+            .submit(path, WriteMode::Create, created.clone())
+            .unwrap();
+        // the file exists by the time this is written
+        let rejected = Data {
+            arr: Array1::from_elem(16, 2),
+            step: 2,
+        };
+        writer.submit(path, WriteMode::Create, rejected).unwrap();
+        assert!(matches!(writer.flush(), Err(Error::OpenFile(_))));
+
+        // the error is only reported once, and later writes still happen
+        let group_file = hdf5::File::create("./async_writer_errors_group.h5").unwrap();
+        let nested = Data {
+            arr: Array1::from_elem(16, 3),
+            step: 3,
+        };
+        writer
+            .submit_group(group_file.create_group("nested").unwrap(), nested.clone())
+            .unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(Data::read_hdf5_from_path(path).unwrap(), created);
+        assert_eq!(
+            Data::read_hdf5(&group_file.group("nested").unwrap()).unwrap(),
+            nested
+        );
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file("./async_writer_errors_group.h5").unwrap();
+    }
+}
@@ -0,0 +1,97 @@
+use std::cell::RefCell;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::error;
+use crate::Error;
+
+/// A flag that stops a derived read or write between fields.
+///
+/// While a closure runs inside [`CancellationToken::run`], every derived `read_hdf5` / `write_hdf5`
+/// (and their variants) checks the token before reading or writing each field, and returns
+/// [`Error::Cancelled`] once the token has been cancelled. A field that is already being
+/// read or written is finished first. Tokens are cheap to clone, and all clones share the same flag,
+/// so a token can be cancelled from a different thread than the one doing the I/O.
+///
+/// ```
+/// use hdf5_derive::{CancellationToken, ContainerWrite, Error};
+/// use ndarray::Array2;
+///
+/// #[derive(ContainerWrite)]
+/// struct Data {
+///     first: Array2<f64>,
+///     second: Array2<f64>,
+/// }
+///
+/// let file = hdf5_derive::memory::create_in_memory().unwrap();
+/// let data = Data { first: Array2::zeros((4, 4)), second: Array2::zeros((4, 4)) };
+///
+/// let token = CancellationToken::new();
+/// token.cancel();
+///
+/// let result = token.run(|| data.write_hdf5(&file));
+/// assert!(matches!(result, Err(Error::Cancelled(_))));
+/// assert!(!file.link_exists("first"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// construct a token that has not been cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// request that reads and writes running with this token stop before the next field
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// whether or not [`CancellationToken::cancel`] has been called on this token or a clone of it
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// run `f` on the current thread, with derived reads and writes checking this token between fields
+    pub fn run<R, F: FnOnce() -> R>(&self, f: F) -> R {
+        let previous = CURRENT.with(|current| current.replace(Some(self.clone())));
+        let _restore = Restore { previous };
+
+        f()
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+}
+
+/// puts back the token that was active before [`CancellationToken::run`], even if `f` panics
+struct Restore {
+    previous: Option<CancellationToken>,
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        CURRENT.with(|current| *current.borrow_mut() = self.previous.take());
+    }
+}
+
+/// called before each field is read or written. Returns [`Error::Cancelled`] if the token of the
+/// enclosing [`CancellationToken::run`] has been cancelled
+pub(crate) fn check_cancelled(field_name: &str) -> Result<(), Error> {
+    let cancelled = CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    });
+
+    if cancelled {
+        Err(error::Cancelled::new(field_name).into())
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,366 @@
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::error;
+use crate::error::ErrorKind;
+use crate::ContainerRead;
+use crate::ContainerWrite;
+use crate::Error;
+use crate::WriteMode;
+
+/// How many checkpoints a [`Checkpointer`] keeps on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// keep at most `n` checkpoints, deleting those with the smallest step numbers after each
+    /// successful save
+    KeepLast(NonZeroUsize),
+    /// never delete checkpoints
+    KeepAll,
+}
+
+/// A single checkpoint file managed by a [`Checkpointer`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// the step number the checkpoint was saved at
+    pub step: u64,
+    /// location of the checkpoint file
+    pub path: PathBuf,
+}
+
+/// Writes containers to checkpoint files that are either complete or absent, and restores
+/// the most recent one that can be read.
+///
+/// Each checkpoint is written with [`ContainerWrite::write_hdf5`] to a hidden temporary file
+/// in the checkpoint directory, which is flushed to disk and then atomically renamed to
+/// `<prefix>_<step>.h5`. A crash part way through a save only ever leaves the temporary file
+/// behind, never a truncated checkpoint. After every save, old checkpoints are removed according
+/// to the [`Rotation`] policy.
+///
+/// Saving a step removes every checkpoint with a larger step, whatever the policy. After rolling
+/// back to an earlier checkpoint, the checkpoints of the abandoned run are discarded, so
+/// [`Checkpointer::restore_latest`] always restores the most recent save.
+///
+/// ```
+/// use hdf5_derive::{Checkpointer, ContainerRead, ContainerWrite, Rotation};
+/// use ndarray::Array1;
+/// use std::num::NonZeroUsize;
+///
+/// #[derive(ContainerRead, ContainerWrite)]
+/// struct State {
+///     energy: Array1<f64>,
+///     step: u64,
+/// }
+///
+/// let keep = NonZeroUsize::new(2).unwrap();
+/// let checkpointer = Checkpointer::new("./checkpoint_doc", "restart", Rotation::KeepLast(keep));
+///
+/// for step in [100, 200, 300] {
+///     let state = State { energy: Array1::zeros(10), step };
+///     checkpointer.save(step, &state).unwrap();
+/// }
+///
+/// // only the last two checkpoints are kept
+/// assert_eq!(checkpointer.checkpoints().unwrap().len(), 2);
+///
+/// let (checkpoint, state) = checkpointer.restore_latest::<State>().unwrap().unwrap();
+/// assert_eq!(state.step, 300);
+/// assert!(checkpoint.path.ends_with("restart_000300.h5"));
+///
+/// std::fs::remove_dir_all("./checkpoint_doc").unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Checkpointer {
+    directory: PathBuf,
+    prefix: String,
+    rotation: Rotation,
+}
+
+impl Checkpointer {
+    /// store checkpoints named `<prefix>_<step>.h5` in `directory`. The directory is created
+    /// on the first save if it does not exist
+    pub fn new<P: Into<PathBuf>>(directory: P, prefix: &str, rotation: Rotation) -> Self {
+        Self {
+            directory: directory.into(),
+            prefix: prefix.into(),
+            rotation,
+        }
+    }
+
+    /// the directory checkpoints are stored in
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// path of the checkpoint file for `step`
+    pub fn path(&self, step: u64) -> PathBuf {
+        self.directory
+            .join(format!("{}_{:06}.h5", self.prefix, step))
+    }
+
+    /// atomically write `container` as the checkpoint for `step`, then apply the rotation policy
+    ///
+    /// An existing checkpoint for the same step is replaced, and checkpoints with a larger step
+    /// are removed. Returns the new checkpoint.
+    pub fn save<T: ContainerWrite>(&self, step: u64, container: &T) -> Result<Checkpoint, Error> {
+        fs::create_dir_all(&self.directory)
+            .map_err(|e| error::FileIo::new("create directory", &self.directory, e))?;
+
+        let path = self.path(step);
+        container.write_hdf5_to_path(&path, WriteMode::AtomicReplace)?;
+
+        self.rotate(step)?;
+
+        Ok(Checkpoint { step, path })
+    }
+
+    /// list the checkpoints in the directory, ordered by ascending step
+    ///
+    /// Temporary files from saves that did not complete are not listed. A missing directory
+    /// has no checkpoints.
+    pub fn checkpoints(&self) -> Result<Vec<Checkpoint>, Error> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(error::FileIo::new("read directory", &self.directory, e).into()),
+        };
+
+        let mut checkpoints = Vec::new();
+
+        for entry in entries {
+            let entry =
+                entry.map_err(|e| error::FileIo::new("read directory", &self.directory, e))?;
+
+            let file_name = entry.file_name();
+            let step = file_name.to_str().and_then(|name| self.parse_step(name));
+
+            if let Some(step) = step {
+                checkpoints.push(Checkpoint {
+                    step,
+                    path: entry.path(),
+                });
+            }
+        }
+
+        checkpoints.sort_by_key(|checkpoint| checkpoint.step);
+
+        Ok(checkpoints)
+    }
+
+    /// read the newest checkpoint that can be read as `T`
+    ///
+    /// Checkpoints are tried from the largest step down. Checkpoints that are missing or
+    /// incomplete are skipped: files removed since they were listed, files that are not valid
+    /// HDF5 (for example, damaged on disk), and files missing fields of `T` or its
+    /// `write_complete` marker. Any other error, such as a file that cannot be opened because of
+    /// its permissions, is returned. Returns `None` if no checkpoint could be read.
+    pub fn restore_latest<T: ContainerRead>(&self) -> Result<Option<(Checkpoint, T)>, Error> {
+        for checkpoint in self.checkpoints()?.into_iter().rev() {
+            match T::read_hdf5_from_path(&checkpoint.path) {
+                Ok(container) => return Ok(Some((checkpoint, container))),
+                Err(e) if is_incomplete(&checkpoint.path, &e)? => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// delete the checkpoints after `saved`, the step that was just written, and the oldest
+    /// checkpoints that are no longer kept by the rotation policy
+    fn rotate(&self, saved: u64) -> Result<(), Error> {
+        let checkpoints = self.checkpoints()?;
+
+        // checkpoints are sorted by step, so `saved` is the last one kept
+        let num_kept = checkpoints.partition_point(|checkpoint| checkpoint.step <= saved);
+
+        let num_old = match self.rotation {
+            Rotation::KeepLast(keep) => num_kept.saturating_sub(keep.get()),
+            Rotation::KeepAll => 0,
+        };
+
+        for checkpoint in checkpoints[..num_old]
+            .iter()
+            .chain(&checkpoints[num_kept..])
+        {
+            fs::remove_file(&checkpoint.path)
+                .map_err(|e| error::FileIo::new("remove", &checkpoint.path, e))?;
+        }
+
+        Ok(())
+    }
+
+    /// parse the step number from a file name of the form `<prefix>_<step>.h5`
+    fn parse_step(&self, file_name: &str) -> Option<u64> {
+        file_name
+            .strip_prefix(self.prefix.as_str())?
+            .strip_prefix('_')?
+            .strip_suffix(".h5")?
+            .parse()
+            .ok()
+    }
+}
+
+/// whether reading the checkpoint at `path` failed with `error` because the checkpoint is missing
+/// or incomplete, rather than because of a problem that also affects the other checkpoints
+fn is_incomplete(path: &Path, error: &Error) -> Result<bool, Error> {
+    match error {
+        // HDF5 does not say why a file could not be opened, ask the filesystem
+        Error::OpenFile(_) => match fs::File::open(path) {
+            // readable, but not a valid HDF5 file
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(true),
+            Err(e) => Err(error::FileIo::new("open", path, e).into()),
+        },
+        _ => Ok(matches!(
+            error.kind(),
+            ErrorKind::NotFound | ErrorKind::IncompleteWrite
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::Array1;
+
+    #[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+    struct State {
+        energy: Array1<f64>,
+        step: u64,
+    }
+
+    #[test]
+    fn rotation_keeps_latest() {
+        let dir = "./checkpoint_rotation";
+        let checkpointer = Checkpointer::new(
+            dir,
+            "chk",
+            Rotation::KeepLast(NonZeroUsize::new(2).unwrap()),
+        );
+
+        for step in [1, 2, 3, 4] {
+            let state = State {
+                energy: Array1::from_elem(4, step as f64),
+                step,
+            };
+            checkpointer.save(step, &state).unwrap();
+        }
+
+        let steps: Vec<u64> = checkpointer
+            .checkpoints()
+            .unwrap()
+            .iter()
+            .map(|c| c.step)
+            .collect();
+        assert_eq!(steps, vec![3, 4]);
+
+        // no temporary files are left behind
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rollback_discards_later_checkpoints() {
+        let dir = "./checkpoint_rollback";
+        let checkpointer = Checkpointer::new(
+            dir,
+            "chk",
+            Rotation::KeepLast(NonZeroUsize::new(2).unwrap()),
+        );
+
+        for step in [1, 2, 3] {
+            let state = State {
+                energy: Array1::from_elem(4, step as f64),
+                step,
+            };
+            checkpointer.save(step, &state).unwrap();
+        }
+
+        // rolled back to step 1 and saved it again, step 3 belongs to the abandoned run
+        let state = State {
+            energy: Array1::from_elem(4, 1.),
+            step: 1,
+        };
+        let checkpoint = checkpointer.save(1, &state).unwrap();
+        assert!(checkpoint.path.exists());
+
+        let steps: Vec<u64> = checkpointer
+            .checkpoints()
+            .unwrap()
+            .iter()
+            .map(|c| c.step)
+            .collect();
+        assert_eq!(steps, vec![1]);
+
+        let (checkpoint, restored) = checkpointer.restore_latest::<State>().unwrap().unwrap();
+        assert_eq!(checkpoint.step, 1);
+        assert_eq!(restored, state);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn restore_skips_corrupt() {
+        let dir = "./checkpoint_restore_corrupt";
+        let checkpointer = Checkpointer::new(dir, "chk", Rotation::KeepAll);
+
+        assert!(checkpointer.restore_latest::<State>().unwrap().is_none());
+
+        let older = State {
+            energy: Array1::from_elem(4, 10.),
+            step: 10,
+        };
+        let newer = State {
+            energy: Array1::from_elem(4, 20.),
+            step: 20,
+        };
+        checkpointer.save(10, &older).unwrap();
+        checkpointer.save(20, &newer).unwrap();
+
+        // a damaged checkpoint with a newer step, and a stale temporary file
+        fs::write(checkpointer.path(30), b"not an hdf5 file").unwrap();
+        fs::write(Path::new(dir).join(".chk_000040.h5.tmp"), b"partial").unwrap();
+
+        let (checkpoint, restored) = checkpointer.restore_latest::<State>().unwrap().unwrap();
+        assert_eq!(checkpoint.step, 20);
+        assert_eq!(restored, newer);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn restore_returns_other_errors() {
+        #[derive(ContainerWrite)]
+        struct Other {
+            energy: ndarray::Array2<f64>,
+            step: u64,
+        }
+
+        let dir = "./checkpoint_restore_other";
+        let checkpointer = Checkpointer::new(dir, "chk", Rotation::KeepAll);
+
+        let state = State {
+            energy: Array1::from_elem(4, 10.),
+            step: 10,
+        };
+        checkpointer.save(10, &state).unwrap();
+
+        // a complete checkpoint that does not hold a `State` is not skipped
+        let other = Other {
+            energy: ndarray::Array2::zeros((2, 2)),
+            step: 20,
+        };
+        checkpointer.save(20, &other).unwrap();
+
+        let error = checkpointer.restore_latest::<State>().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::DimensionMismatch);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
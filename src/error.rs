@@ -1,14 +1,161 @@
 //! Error types for handling HDF5 data with additional context information
 
+use std::fmt;
+
+use hdf5::Group;
+
+use crate::validate::ObjectKind;
+
+#[derive(Debug, Clone, Default)]
+/// Where in a file an error happened
+///
+/// Filled in as the error passes through the fields of derived containers: the innermost field records the
+/// full HDF5 path and the file name, and every field on the way out adds its Rust field name. Errors that did not
+/// happen inside a derived read or write have an empty context.
+pub struct ErrorContext {
+    // boxed, so that the context does not grow every `Result` holding an error
+    location: Option<Box<Location>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Location {
+    path: Option<String>,
+    file_name: Option<String>,
+    /// Rust field names, outermost first
+    fields: Vec<String>,
+}
+
+impl ErrorContext {
+    /// full HDF5 path of the dataset, attribute or group that failed, such as
+    /// `/supplemental_information/vorticity`
+    pub fn path(&self) -> Option<&str> {
+        self.location.as_ref()?.path.as_deref()
+    }
+
+    /// name of the file holding [`ErrorContext::path`]
+    pub fn file_name(&self) -> Option<&str> {
+        self.location.as_ref()?.file_name.as_deref()
+    }
+
+    /// chain of Rust field names leading to the failure, such as `supplemental_information.vorticity`
+    pub fn field_path(&self) -> Option<String> {
+        let fields = &self.location.as_ref()?.fields;
+
+        if fields.is_empty() {
+            None
+        } else {
+            Some(fields.join("."))
+        }
+    }
+
+    /// whether or not nothing is known about where the error happened
+    pub fn is_empty(&self) -> bool {
+        self.location.is_none()
+    }
+
+    /// record that the error happened while reading or writing the field `field`, stored as `name` in `group`
+    pub(crate) fn push_field(&mut self, group: &Group, name: &str, field: &str) {
+        let location = self.location.get_or_insert_with(Default::default);
+
+        if location.path.is_none() {
+            let group_path = group.name();
+
+            location.path = Some(if group_path.ends_with('/') {
+                format!("{group_path}{name}")
+            } else {
+                format!("{group_path}/{name}")
+            });
+
+            location.file_name = Some(group.filename());
+        }
+
+        location.fields.insert(0, field.to_string());
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    /// nothing for an empty context, otherwise a paragraph to append to an error message
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        write!(f, "\n\nlocation:")?;
+
+        if let Some(path) = self.path() {
+            write!(f, " `{path}`")?;
+        }
+        if let Some(fields) = self.field_path() {
+            write!(f, " (field `{fields}`)")?;
+        }
+        if let Some(file_name) = self.file_name() {
+            write!(f, " in file `{file_name}`")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+/// Broad category of an [`crate::Error`], returned by [`crate::Error::kind`]
+pub enum ErrorKind {
+    /// a dataset, attribute or group that should already exist is missing
+    NotFound,
+    /// a dataset has a different number of dimensions than the array it is read into
+    DimensionMismatch,
+    /// a dataset has a different shape than the array written to it
+    ShapeMismatch,
+    /// a field is stored as a different kind of object, such as a group where a dataset was expected
+    WrongObjectKind,
+    /// the `units` attribute of a field does not match the units declared in Rust
+    UnitsMismatch,
+    /// several fields failed, see [`Aggregate`]
+    Multiple,
+    /// a dataset has a datatype that cannot be converted to the element type of the array
+    DatatypeMismatch,
+    /// HDF5 failed to read data that exists
+    Read,
+    /// HDF5 failed to create or write data
+    Write,
+    /// a file could not be opened, closed, renamed, mapped or copied
+    File,
+    /// the operation is not possible for the layout of the dataset
+    Unsupported,
+    /// the container was not completely written
+    IncompleteWrite,
+    /// the operation was cancelled
+    Cancelled,
+    /// a background thread or task failed
+    Task,
+}
+
+/// accessors for the [`ErrorContext`] of an error type
+macro_rules! impl_context {
+    ($error_name:ident) => {
+        impl $error_name {
+            /// where in the file the error happened
+            pub fn context(&self) -> &ErrorContext {
+                &self.context
+            }
+
+            pub(crate) fn context_mut(&mut self) -> &mut ErrorContext {
+                &mut self.context
+            }
+        }
+    };
+}
+
 macro_rules! create_error_type {
     ($error_name:ident, $format_str:expr, $doc_str:expr) => {
         #[doc=$doc_str]
         #[derive(thiserror::Error, Debug)]
-        #[error("{}\n\nsource:\n{}", .msg, .source)]
+        #[error("{}{}\n\nsource:\n{}", .msg, .context, .source)]
         pub struct $error_name {
             msg: String,
             #[source]
             source: hdf5::Error,
+            context: ErrorContext,
         }
 
         impl $error_name {
@@ -16,9 +163,15 @@ macro_rules! create_error_type {
             /// the corresponding HDF5 error
             pub fn from_field_name(name: &str, source: hdf5::Error) -> Self {
                 let msg = format!($format_str, name);
-                Self { msg, source }
+                Self {
+                    msg,
+                    source,
+                    context: ErrorContext::default(),
+                }
             }
         }
+
+        impl_context!($error_name);
     };
 }
 
@@ -104,6 +257,32 @@ create_error_type! {
     "Failed to fetch the datatype of a given dataset"
 }
 
+create_error_type! {
+    OpenFile,
+    "Failed to open file `{}`",
+    "Could not open or create an HDF5 file"
+}
+
+create_error_type! {
+    CloseFile,
+    "Failed to close file `{}`",
+    "Could not flush and close an HDF5 file"
+}
+
+impl OpenFile {
+    /// construct an instance of this type using the path of the file and the corresponding HDF5 error
+    pub fn from_path(path: &std::path::Path, source: hdf5::Error) -> Self {
+        Self::from_field_name(&path.display().to_string(), source)
+    }
+}
+
+impl CloseFile {
+    /// construct an instance of this type using the path of the file and the corresponding HDF5 error
+    pub fn from_path(path: &std::path::Path, source: hdf5::Error) -> Self {
+        Self::from_field_name(&path.display().to_string(), source)
+    }
+}
+
 create_error_type! {
     ReadSlice,
     "Failed to read slice for dataarray `{}`",
@@ -116,44 +295,502 @@ create_error_type! {
     "Failed to write a slice of data to an HDF5 dataset"
 }
 
+create_error_type! {
+    FileImage,
+    "Failed to copy the file image of `{}`",
+    "Could not copy an HDF5 file to or from an in-memory file image"
+}
+
+create_error_type! {
+    StartSwmr,
+    "Failed to switch file `{}` to single-writer / multiple-reader mode",
+    "Could not switch an HDF5 file to single-writer / multiple-reader mode"
+}
+
+create_error_type! {
+    FlushDataset,
+    "Failed to flush dataset `{}` to the file",
+    "Failed to flush a dataset written in single-writer / multiple-reader mode"
+}
+
+create_error_type! {
+    RefreshDataset,
+    "Failed to refresh dataset `{}` from the file",
+    "Failed to refresh a dataset read in single-writer / multiple-reader mode"
+}
+
 #[derive(thiserror::Error, Debug)]
-#[error("dimensions for array `{array_name}` were incorrect. Dataset was dimension `{dataset_dimension}`, not the specified dimension `{specified_dimension}`")]
-/// [`crate::lazy_array::LazyArray`] underlying [`hdf5::Dataset`] was not the correct dimension
+#[error("dimensions for array `{array_name}` were incorrect. Dataset was dimension `{dataset_dimension}`, not the specified dimension `{specified_dimension}`{context}")]
+/// The dataset backing an array or [`crate::LazyArray`] has a different number of dimensions than the Rust type
 pub struct DimensionMismatch {
     array_name: String,
     dataset_dimension: usize,
     specified_dimension: usize,
+    context: ErrorContext,
 }
 
 impl DimensionMismatch {
     /// constructor for this type
     ///
-    /// `array_name` is the dataset name that would back this lazy array
+    /// `array_name` is the name of the dataset that would back the array
     pub fn new(array_name: &str, dataset_dimension: usize, specified_dimension: usize) -> Self {
         Self {
             array_name: array_name.into(),
             dataset_dimension,
             specified_dimension,
+            context: ErrorContext::default(),
         }
     }
+
+    /// name of the dataset
+    pub fn array_name(&self) -> &str {
+        &self.array_name
+    }
+
+    /// number of dimensions of the Rust type
+    pub fn expected(&self) -> usize {
+        self.specified_dimension
+    }
+
+    /// number of dimensions of the dataset
+    pub fn actual(&self) -> usize {
+        self.dataset_dimension
+    }
 }
 
+impl_context!(DimensionMismatch);
+
 #[derive(thiserror::Error, Debug)]
-#[error("Specified datatype for array `{array_name}` was incorrect. Array type was {datatype:?}")]
-/// Datatype for [`crate::LazyArray`] was incorrect
+#[error("Specified datatype `{expected}` for array `{array_name}` was incorrect. Array type was {datatype:?}{context}")]
+/// The datatype of the dataset backing an array or [`crate::LazyArray`] cannot be read as the element type of
+/// the Rust type
 pub struct WrongDatatype {
     array_name: String,
+    expected: hdf5::types::TypeDescriptor,
     datatype: hdf5::datatype::Datatype,
+    context: ErrorContext,
 }
 
 impl WrongDatatype {
     /// constructor for this type
     ///
-    /// `array_name` is the dataset name that would back this lazy array
-    pub fn new(array_name: &str, datatype: hdf5::datatype::Datatype) -> Self {
+    /// `array_name` is the name of the dataset that would back the array, `expected` the element type of
+    /// the array, and `datatype` the datatype of the dataset
+    pub fn new(
+        array_name: &str,
+        expected: hdf5::types::TypeDescriptor,
+        datatype: hdf5::datatype::Datatype,
+    ) -> Self {
         Self {
             array_name: array_name.into(),
+            expected,
             datatype,
+            context: ErrorContext::default(),
+        }
+    }
+
+    /// name of the dataset
+    pub fn array_name(&self) -> &str {
+        &self.array_name
+    }
+
+    /// element type of the Rust type
+    pub fn expected(&self) -> &hdf5::types::TypeDescriptor {
+        &self.expected
+    }
+
+    /// datatype of the dataset
+    pub fn actual(&self) -> &hdf5::datatype::Datatype {
+        &self.datatype
+    }
+}
+
+impl_context!(WrongDatatype);
+
+#[derive(thiserror::Error, Debug)]
+#[error("shape of array `{array_name}` was incorrect. Dataset has shape {actual:?}, but the array has shape {expected:?}{context}")]
+/// An array written with `mutate_on_write` has a different shape than the existing dataset
+pub struct ShapeMismatch {
+    array_name: String,
+    expected: Vec<usize>,
+    actual: Vec<usize>,
+    context: ErrorContext,
+}
+
+impl ShapeMismatch {
+    /// constructor for this type
+    ///
+    /// `expected` is the shape of the array, and `actual` the shape of the dataset
+    pub fn new(array_name: &str, expected: &[usize], actual: &[usize]) -> Self {
+        Self {
+            array_name: array_name.into(),
+            expected: expected.to_vec(),
+            actual: actual.to_vec(),
+            context: ErrorContext::default(),
+        }
+    }
+
+    /// name of the dataset
+    pub fn array_name(&self) -> &str {
+        &self.array_name
+    }
+
+    /// shape of the array
+    pub fn expected(&self) -> &[usize] {
+        &self.expected
+    }
+
+    /// shape of the dataset
+    pub fn actual(&self) -> &[usize] {
+        &self.actual
+    }
+}
+
+impl_context!(ShapeMismatch);
+
+#[derive(thiserror::Error, Debug)]
+#[error("dataset for array `{array_name}` cannot be memory mapped: {reason}{context}")]
+/// The storage layout of a [`crate::LazyArray`]'s dataset does not allow it to be memory mapped
+pub struct MmapUnsupported {
+    array_name: String,
+    reason: String,
+    context: ErrorContext,
+}
+
+impl MmapUnsupported {
+    /// constructor for this type
+    ///
+    /// `reason` describes which property of the dataset prevents mapping it
+    pub fn new(array_name: &str, reason: impl Into<String>) -> Self {
+        Self {
+            array_name: array_name.into(),
+            reason: reason.into(),
+            context: ErrorContext::default(),
         }
     }
 }
+
+impl_context!(MmapUnsupported);
+
+#[derive(thiserror::Error, Debug)]
+#[error("failed to memory map the file `{file_name}` backing array `{array_name}`{context}")]
+/// Failed to open or memory map the file that backs a [`crate::LazyArray`]
+pub struct MapFile {
+    array_name: String,
+    file_name: String,
+    #[source]
+    source: std::io::Error,
+    context: ErrorContext,
+}
+
+impl MapFile {
+    /// constructor for this type
+    pub fn new(array_name: &str, file_name: &str, source: std::io::Error) -> Self {
+        Self {
+            array_name: array_name.into(),
+            file_name: file_name.into(),
+            source,
+            context: ErrorContext::default(),
+        }
+    }
+}
+
+impl_context!(MapFile);
+
+#[derive(thiserror::Error, Debug)]
+#[error("failed to {operation} `{}`{context}", .path.display())]
+/// A filesystem operation on an HDF5 file (or the directory holding it) failed
+pub struct FileIo {
+    operation: String,
+    path: std::path::PathBuf,
+    #[source]
+    source: std::io::Error,
+    context: ErrorContext,
+}
+
+impl FileIo {
+    /// constructor for this type
+    ///
+    /// `operation` is a short description of what was being done to `path`, such as `"rename"`
+    pub fn new(operation: &str, path: &std::path::Path, source: std::io::Error) -> Self {
+        Self {
+            operation: operation.into(),
+            path: path.into(),
+            source,
+            context: ErrorContext::default(),
+        }
+    }
+}
+
+impl_context!(FileIo);
+
+#[derive(thiserror::Error, Debug)]
+#[error("group `{group_name}` is missing the `write_complete` marker, it may not have been completely written{context}")]
+/// A container with `#[hdf5(write_complete)]` was read from a group without the marker set
+pub struct IncompleteWrite {
+    group_name: String,
+    context: ErrorContext,
+}
+
+impl IncompleteWrite {
+    /// constructor for this type
+    pub fn new(group_name: &str) -> Self {
+        Self {
+            group_name: group_name.into(),
+            context: ErrorContext::default(),
+        }
+    }
+}
+
+impl_context!(IncompleteWrite);
+
+#[derive(thiserror::Error, Debug)]
+#[error("background writer failed: {reason}{context}")]
+/// The thread of a [`crate::AsyncWriter`] stopped unexpectedly
+pub struct BackgroundWriter {
+    reason: String,
+    context: ErrorContext,
+}
+
+impl BackgroundWriter {
+    /// constructor for this type
+    pub fn new(reason: &str) -> Self {
+        Self {
+            reason: reason.into(),
+            context: ErrorContext::default(),
+        }
+    }
+}
+
+impl_context!(BackgroundWriter);
+
+#[derive(thiserror::Error, Debug)]
+#[error("cancelled before `{field_name}` was read or written{context}")]
+/// A [`crate::CancellationToken`] was cancelled while a container was being read or written
+pub struct Cancelled {
+    field_name: String,
+    context: ErrorContext,
+}
+
+impl Cancelled {
+    /// constructor for this type
+    pub fn new(field_name: &str) -> Self {
+        Self {
+            field_name: field_name.into(),
+            context: ErrorContext::default(),
+        }
+    }
+}
+
+impl_context!(Cancelled);
+
+#[derive(thiserror::Error, Debug)]
+#[error("blocking task failed: {reason}{context}")]
+/// A blocking task spawned by an async wrapper panicked, or was aborted by the runtime
+pub struct BlockingTask {
+    reason: String,
+    context: ErrorContext,
+}
+
+impl BlockingTask {
+    /// constructor for this type
+    pub fn new(reason: &str) -> Self {
+        Self {
+            reason: reason.into(),
+            context: ErrorContext::default(),
+        }
+    }
+}
+
+impl_context!(BlockingTask);
+
+#[derive(thiserror::Error, Debug)]
+#[error("expected `{name}` to be a {expected}, but it is a {actual}{context}")]
+/// A field is stored as a different kind of HDF5 object than its Rust type expects
+pub struct WrongObjectKind {
+    name: String,
+    expected: ObjectKind,
+    actual: ObjectKind,
+    context: ErrorContext,
+}
+
+impl WrongObjectKind {
+    /// constructor for this type
+    pub fn new(name: &str, expected: ObjectKind, actual: ObjectKind) -> Self {
+        Self {
+            name: name.into(),
+            expected,
+            actual,
+            context: ErrorContext::default(),
+        }
+    }
+
+    /// name of the object
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the kind of object the Rust type is read from
+    pub fn expected(&self) -> ObjectKind {
+        self.expected
+    }
+
+    /// the kind of object in the file
+    pub fn actual(&self) -> ObjectKind {
+        self.actual
+    }
+}
+
+impl_context!(WrongObjectKind);
+
+#[derive(thiserror::Error, Debug)]
+#[error("`{array_name}` cannot be transposed{context}")]
+/// A field that does not support transposing, such as a [`crate::LazyArray`], is marked with `transpose`
+pub struct TransposeUnsupported {
+    array_name: String,
+    context: ErrorContext,
+}
+
+impl TransposeUnsupported {
+    /// constructor for this type
+    pub fn new(array_name: &str) -> Self {
+        Self {
+            array_name: array_name.into(),
+            context: ErrorContext::default(),
+        }
+    }
+}
+
+impl_context!(TransposeUnsupported);
+
+#[derive(thiserror::Error, Debug)]
+#[error("`{type_name}` does not implement {operation}{context}")]
+/// A hand-written container does not implement one of the optional methods of
+/// [`crate::ContainerRead`] or [`crate::ContainerWrite`]
+pub struct OperationUnsupported {
+    type_name: &'static str,
+    operation: &'static str,
+    context: ErrorContext,
+}
+
+impl OperationUnsupported {
+    /// constructor for this type
+    ///
+    /// `operation` is the name of the method that is not implemented
+    pub fn new(type_name: &'static str, operation: &'static str) -> Self {
+        Self {
+            type_name,
+            operation,
+            context: ErrorContext::default(),
+        }
+    }
+}
+
+impl_context!(OperationUnsupported);
+
+#[derive(thiserror::Error, Debug)]
+#[error("`{name}` is not a dataset or group, so attributes cannot be attached to it{context}")]
+/// Attributes were attached to a field stored as an attribute, such as a scalar with `attrs(..)` or a
+/// [`crate::WithAttrs`] wrapping a scalar
+pub struct AttributesUnsupported {
+    name: String,
+    context: ErrorContext,
+}
+
+impl AttributesUnsupported {
+    /// constructor for this type
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            context: ErrorContext::default(),
+        }
+    }
+}
+
+impl_context!(AttributesUnsupported);
+
+#[derive(thiserror::Error, Debug)]
+#[error("expected `{name}` to be in units of `{expected}`, but {}{context}", describe_units(.actual))]
+/// The `units` attribute of a field marked with `check_units` is missing or different from the declared units
+pub struct UnitsMismatch {
+    name: String,
+    expected: String,
+    actual: Option<String>,
+    context: ErrorContext,
+}
+
+impl UnitsMismatch {
+    /// constructor for this type
+    pub fn new(name: &str, expected: &str, actual: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            expected: expected.into(),
+            actual,
+            context: ErrorContext::default(),
+        }
+    }
+
+    /// name of the field in the file
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the units declared on the Rust field
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    /// the units in the file, `None` if the field has no `units` attribute
+    pub fn actual(&self) -> Option<&str> {
+        self.actual.as_deref()
+    }
+}
+
+impl_context!(UnitsMismatch);
+
+/// what the file says about the units of a field
+fn describe_units(actual: &Option<String>) -> String {
+    match actual {
+        Some(actual) => format!("the file stores it in `{actual}`"),
+        None => "the file does not record its units".into(),
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("{} fields could not be read:{}{context}", .errors.len(), numbered(.errors))]
+/// Every error found by [`crate::ContainerRead::read_hdf5_report`], including those in nested containers
+pub struct Aggregate {
+    errors: Vec<crate::Error>,
+    context: ErrorContext,
+}
+
+impl Aggregate {
+    /// constructor for this type
+    pub fn new(errors: Vec<crate::Error>) -> Self {
+        Self {
+            errors,
+            context: ErrorContext::default(),
+        }
+    }
+
+    /// the errors, in field order. Each has its [`crate::Error::path`] and [`crate::Error::field_path`] set
+    pub fn errors(&self) -> &[crate::Error] {
+        &self.errors
+    }
+
+    /// take the errors out of the aggregate
+    pub fn into_errors(self) -> Vec<crate::Error> {
+        self.errors
+    }
+}
+
+impl_context!(Aggregate);
+
+/// each error in its own numbered paragraph
+fn numbered(errors: &[crate::Error]) -> String {
+    errors
+        .iter()
+        .enumerate()
+        .map(|(index, error)| format!("\n\n{}. {error}", index + 1))
+        .collect()
+}
@@ -0,0 +1,218 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use hdf5::File;
+
+use crate::error;
+use crate::ContainerRead;
+use crate::ContainerWrite;
+use crate::Error;
+
+/// How [`ContainerWrite::write_hdf5_to_path`] opens the file it writes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteMode {
+    /// create a new file, failing if the file already exists
+    Create,
+    /// create a new file, discarding the contents of the file if it already exists
+    Truncate,
+    /// write into an existing file, creating it if it does not exist. Fields are written as
+    /// they would be with [`hdf5::File::append`], so datasets that already exist must be marked with
+    /// `#[hdf5(mutate_on_write)]`
+    Append,
+    /// write to a temporary file next to the destination, flush it to disk, and atomically
+    /// rename it over the destination. Other processes see either the previous file or the
+    /// complete new file, never a partially written one
+    AtomicReplace,
+}
+
+/// write `container` to `path`, opening the file according to `mode`
+pub(crate) fn write_to_path<T: ContainerWrite>(
+    container: &T,
+    path: &Path,
+    mode: WriteMode,
+) -> Result<(), Error> {
+    let file = match mode {
+        WriteMode::Create => File::create_excl(path),
+        WriteMode::Truncate => File::create(path),
+        WriteMode::Append => File::append(path),
+        WriteMode::AtomicReplace => return write_atomic(container, path),
+    }
+    .map_err(|e| error::OpenFile::from_path(path, e))?;
+
+    container.write_hdf5(&file)?;
+
+    file.close()
+        .map_err(|e| error::CloseFile::from_path(path, e))?;
+
+    Ok(())
+}
+
+/// open `path` read-only and read `T` from the root group
+pub(crate) fn read_from_path<T: ContainerRead>(path: &Path) -> Result<T, Error> {
+    let file = File::open(path).map_err(|e| error::OpenFile::from_path(path, e))?;
+
+    T::read_hdf5(&file)
+}
+
+/// write `container` to a hidden temporary file in the same directory as `path`, flush it to
+/// disk, then rename it to `path`. The temporary file is removed if writing fails
+fn write_atomic<T: ContainerWrite>(container: &T, path: &Path) -> Result<(), Error> {
+    let temp_path = temp_path(path);
+
+    if let Err(e) = write_synced(container, &temp_path) {
+        fs::remove_file(&temp_path).ok();
+        return Err(e);
+    }
+
+    fs::rename(&temp_path, path).map_err(|e| error::FileIo::new("rename", &temp_path, e))?;
+
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    sync_directory(directory)
+}
+
+/// a temporary file for atomically replacing `path`: `dir/.name.<pid>.<count>.tmp` for `dir/name`, so that
+/// concurrent replacements of the same file, from this process or others, never share a temporary file
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNT.fetch_add(1, Ordering::Relaxed);
+
+    let mut file_name = std::ffi::OsString::from(".");
+    file_name.push(path.file_name().unwrap_or_default());
+    file_name.push(format!(".{}.{count}.tmp", std::process::id()));
+
+    path.with_file_name(file_name)
+}
+
+/// write `container` to a new file at `path` and flush it to disk
+fn write_synced<T: ContainerWrite>(container: &T, path: &Path) -> Result<(), Error> {
+    write_to_path(container, path, WriteMode::Truncate)?;
+
+    fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.sync_all())
+        .map_err(|e| error::FileIo::new("sync", path, e))?;
+
+    Ok(())
+}
+
+/// flush a rename in `directory` to disk
+#[cfg(unix)]
+fn sync_directory(directory: &Path) -> Result<(), Error> {
+    fs::File::open(directory)
+        .and_then(|dir| dir.sync_all())
+        .map_err(|e| error::FileIo::new("sync", directory, e))?;
+
+    Ok(())
+}
+
+/// directories cannot be opened for syncing on this platform, the rename is left to the OS
+#[cfg(not(unix))]
+fn sync_directory(_directory: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::Array1;
+
+    #[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+    struct Data {
+        arr: Array1<u32>,
+        scalar: u32,
+    }
+
+    #[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+    struct Extra {
+        other: Array1<f64>,
+    }
+
+    #[test]
+    fn write_modes() {
+        let path = "./file_io_write_modes.h5";
+        fs::remove_file(path).ok();
+
+        let created = Data {
+            arr: Array1::from_elem(3, 1),
+            scalar: 1,
+        };
+        created.write_hdf5_to_path(path, WriteMode::Create).unwrap();
+        assert_eq!(Data::read_hdf5_from_path(path).unwrap(), created);
+
+        // the file already exists
+        let err = created
+            .write_hdf5_to_path(path, WriteMode::Create)
+            .unwrap_err();
+        assert!(err.to_string().contains(path));
+
+        let truncated = Data {
+            arr: Array1::from_elem(3, 3),
+            scalar: 3,
+        };
+        truncated
+            .write_hdf5_to_path(path, WriteMode::Truncate)
+            .unwrap();
+        assert_eq!(Data::read_hdf5_from_path(path).unwrap(), truncated);
+
+        let replaced = Data {
+            arr: Array1::from_elem(3, 4),
+            scalar: 4,
+        };
+        replaced
+            .write_hdf5_to_path(path, WriteMode::AtomicReplace)
+            .unwrap();
+        assert_eq!(Data::read_hdf5_from_path(path).unwrap(), replaced);
+        let leftovers = fs::read_dir(".")
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy()
+                    .starts_with(".file_io_write_modes.h5.")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        // add a new dataset next to the existing ones
+        let extra = Extra {
+            other: Array1::zeros(2),
+        };
+        extra.write_hdf5_to_path(path, WriteMode::Append).unwrap();
+        assert_eq!(Extra::read_hdf5_from_path(path).unwrap(), extra);
+        assert_eq!(Data::read_hdf5_from_path(path).unwrap(), replaced);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn temp_paths_unique() {
+        let path = Path::new("dir/data.h5");
+        let (first, second) = (temp_path(path), temp_path(path));
+
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), Some(Path::new("dir")));
+        assert!(first
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(".data.h5."));
+    }
+
+    #[test]
+    fn read_missing_file() {
+        let path = "./file_io_missing.h5";
+
+        let err = Data::read_hdf5_from_path(path).err().unwrap();
+        assert!(matches!(err, Error::OpenFile(_)));
+        assert!(err.to_string().contains(path));
+    }
+}
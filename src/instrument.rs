@@ -0,0 +1,402 @@
+//! `tracing` spans for every field, dataset and attribute transfer, and aggregated I/O statistics
+//!
+//! Requires the `tracing` feature.
+//!
+//! Every field read or written by a derived container is wrapped in an `INFO` level `hdf5_field` span,
+//! with the path of the group holding it, the name of the field, and (once it is done) the bytes moved and
+//! the duration in microseconds. Every dataset and attribute transfer inside it is wrapped in a `DEBUG` level
+//! `hdf5_transfer` span, which also records the dataset name, shape and datatype.
+//!
+//! Independently of any subscriber, the totals of every dataset and attribute transfer are accumulated per
+//! HDF5 path, and returned by [`io_report`]. The report lists the slowest paths first, which makes it easy
+//! to see which fields dominate the time spent writing a checkpoint:
+//!
+//! ```
+//! use hdf5_derive::ContainerWrite;
+//! use hdf5_derive::instrument::{io_report, reset_io_stats};
+//! use ndarray::Array2;
+//!
+//! #[derive(ContainerWrite)]
+//! struct Checkpoint {
+//!     velocity: Array2<f64>,
+//!     step: u64,
+//! }
+//!
+//! reset_io_stats();
+//!
+//! let file = hdf5_derive::memory::create_in_memory().unwrap();
+//! let checkpoint = Checkpoint { velocity: Array2::zeros((100, 100)), step: 1 };
+//! checkpoint.write_hdf5(&file).unwrap();
+//!
+//! let report = io_report();
+//! let velocity = report.entries.iter().find(|entry| entry.path == "/velocity").unwrap();
+//! assert_eq!(velocity.bytes, 100 * 100 * 8);
+//!
+//! // a table of paths, operations, counts, bytes, durations and throughput
+//! println!("{report}");
+//! ```
+//!
+//! Statistics are collected from every thread in the process. Reset them with [`reset_io_stats`].
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use hdf5::Group;
+use hdf5::H5Type;
+
+use tracing::field::Empty;
+
+use crate::progress::Operation;
+
+/// totals of every transfer made so far, keyed by HDF5 path and operation
+static STATS: Mutex<BTreeMap<(String, Operation), Totals>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Totals {
+    count: u64,
+    bytes: u64,
+    duration: Duration,
+}
+
+#[derive(Debug, Clone)]
+/// Aggregated transfers to or from a single dataset or attribute
+pub struct IoStat {
+    /// full HDF5 path of the dataset or attribute
+    pub path: String,
+    /// reading or writing
+    pub operation: Operation,
+    /// number of transfers
+    pub count: u64,
+    /// bytes moved over all transfers
+    pub bytes: u64,
+    /// time spent over all transfers
+    pub duration: Duration,
+}
+
+impl IoStat {
+    /// average bytes per second over all transfers
+    pub fn throughput(&self) -> f64 {
+        let seconds = self.duration.as_secs_f64();
+
+        if seconds > 0. {
+            self.bytes as f64 / seconds
+        } else {
+            0.
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Snapshot of the I/O statistics collected so far, returned by [`io_report`]
+pub struct IoReport {
+    /// one entry per path and operation, slowest first
+    pub entries: Vec<IoStat>,
+}
+
+impl IoReport {
+    /// bytes moved over all entries
+    pub fn total_bytes(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.bytes).sum()
+    }
+
+    /// time spent over all entries
+    pub fn total_duration(&self) -> Duration {
+        self.entries.iter().map(|entry| entry.duration).sum()
+    }
+}
+
+impl fmt::Display for IoReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total_duration().as_secs_f64();
+
+        writeln!(
+            f,
+            "{:<40} {:>5} {:>7} {:>14} {:>12} {:>7} {:>12}",
+            "path", "op", "count", "bytes", "seconds", "share", "MB/s"
+        )?;
+
+        for entry in &self.entries {
+            let seconds = entry.duration.as_secs_f64();
+            let share = if total > 0. {
+                100. * seconds / total
+            } else {
+                0.
+            };
+            let operation = match entry.operation {
+                Operation::Read => "read",
+                Operation::Write => "write",
+            };
+
+            writeln!(
+                f,
+                "{:<40} {:>5} {:>7} {:>14} {:>12.6} {:>6.1}% {:>12.1}",
+                entry.path,
+                operation,
+                entry.count,
+                entry.bytes,
+                seconds,
+                share,
+                entry.throughput() / 1e6
+            )?;
+        }
+
+        write!(
+            f,
+            "total: {} bytes in {:.6} seconds",
+            self.total_bytes(),
+            total
+        )
+    }
+}
+
+/// the I/O statistics collected since the start of the process, or the last [`reset_io_stats`]
+pub fn io_report() -> IoReport {
+    let stats = STATS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let mut entries: Vec<IoStat> = stats
+        .iter()
+        .map(|((path, operation), totals)| IoStat {
+            path: path.clone(),
+            operation: *operation,
+            count: totals.count,
+            bytes: totals.bytes,
+            duration: totals.duration,
+        })
+        .collect();
+
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.duration));
+
+    IoReport { entries }
+}
+
+/// discard the I/O statistics collected so far
+pub fn reset_io_stats() {
+    STATS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clear();
+}
+
+/// span around a single field of a derived container, recording the bytes moved and the duration when dropped
+pub(crate) struct FieldSpan {
+    span: tracing::span::EnteredSpan,
+    bytes_before: u64,
+    start: Instant,
+}
+
+impl FieldSpan {
+    pub(crate) fn enter(group: &Group, field: &str, operation: Operation) -> Self {
+        let span = tracing::info_span!(
+            "hdf5_field",
+            group = Empty,
+            field,
+            operation = ?operation,
+            bytes = Empty,
+            duration_us = Empty,
+        );
+
+        // fetching the name of the group is an HDF5 call, so skip it if nobody is listening
+        if !span.is_disabled() {
+            span.record("group", group.name().as_str());
+        }
+
+        Self {
+            span: span.entered(),
+            bytes_before: crate::progress::recorded_bytes(),
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for FieldSpan {
+    fn drop(&mut self) {
+        let bytes = crate::progress::recorded_bytes().wrapping_sub(self.bytes_before);
+
+        self.span.record("bytes", bytes);
+        self.span
+            .record("duration_us", self.start.elapsed().as_micros() as u64);
+    }
+}
+
+/// span around a single dataset or attribute transfer. The transfer is added to the statistics when
+/// dropped, if its shape was recorded (which only happens once the data has been moved)
+pub(crate) struct Transfer<'a> {
+    span: tracing::span::EnteredSpan,
+    operation: Operation,
+    group: &'a Group,
+    name: &'a str,
+    element_size: usize,
+    bytes: Option<u64>,
+    start: Instant,
+}
+
+impl<'a> Transfer<'a> {
+    /// start transferring the dataset or attribute `name` of `group`, with elements of type `T`
+    pub(crate) fn start<T: H5Type>(operation: Operation, group: &'a Group, name: &'a str) -> Self {
+        let dtype = T::type_descriptor();
+
+        let span = tracing::debug_span!(
+            "hdf5_transfer",
+            group = Empty,
+            dataset = name,
+            operation = ?operation,
+            dtype = %dtype,
+            shape = Empty,
+            bytes = Empty,
+            duration_us = Empty,
+        );
+
+        // fetching the name of the group is an HDF5 call, so skip it if nobody is listening
+        if !span.is_disabled() {
+            span.record("group", group.name().as_str());
+        }
+
+        Self {
+            span: span.entered(),
+            operation,
+            group,
+            name,
+            element_size: std::mem::size_of::<T>(),
+            bytes: None,
+            start: Instant::now(),
+        }
+    }
+
+    /// record the shape of the data that was moved. Scalar attributes have an empty shape
+    pub(crate) fn shape(&mut self, shape: &[usize]) {
+        let bytes = (shape.iter().product::<usize>() * self.element_size) as u64;
+
+        self.span.record("shape", tracing::field::debug(shape));
+        self.span.record("bytes", bytes);
+        self.bytes = Some(bytes);
+    }
+}
+
+impl Drop for Transfer<'_> {
+    fn drop(&mut self) {
+        let duration = self.start.elapsed();
+        self.span.record("duration_us", duration.as_micros() as u64);
+
+        let bytes = match self.bytes {
+            Some(bytes) => bytes,
+            // the transfer failed
+            None => return,
+        };
+
+        // the statistics are kept without a subscriber, so completed transfers always need their path
+        let group_path = self.group.name();
+        let path = if group_path.ends_with('/') {
+            format!("{group_path}{}", self.name)
+        } else {
+            format!("{group_path}/{}", self.name)
+        };
+
+        let mut stats = STATS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let totals = stats.entry((path, self.operation)).or_default();
+
+        totals.count += 1;
+        totals.bytes += bytes;
+        totals.duration += duration;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContainerRead, ContainerWrite};
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::Array2;
+
+    // statistics are shared by every test in the process, so the names here are unique to this test
+    #[derive(ContainerRead, ContainerWrite)]
+    struct InstrumentNested {
+        instrument_pressure: Array2<f32>,
+    }
+
+    #[derive(ContainerRead, ContainerWrite)]
+    struct InstrumentData {
+        instrument_velocity: Array2<f64>,
+        instrument_nested: InstrumentNested,
+        instrument_step: u16,
+    }
+
+    fn find(report: &IoReport, path: &str, operation: Operation) -> IoStat {
+        report
+            .entries
+            .iter()
+            .find(|entry| entry.path == path && entry.operation == operation)
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    fn collects_stats() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        let data = InstrumentData {
+            instrument_velocity: Array2::zeros((6, 5)),
+            instrument_nested: InstrumentNested {
+                instrument_pressure: Array2::zeros((2, 2)),
+            },
+            instrument_step: 3,
+        };
+
+        data.write_hdf5(&file).unwrap();
+        InstrumentData::read_hdf5(&file).unwrap();
+        InstrumentData::read_hdf5(&file).unwrap();
+
+        let report = io_report();
+
+        let velocity = find(&report, "/instrument_velocity", Operation::Write);
+        assert_eq!((velocity.count, velocity.bytes), (1, 30 * 8));
+
+        let pressure = find(
+            &report,
+            "/instrument_nested/instrument_pressure",
+            Operation::Read,
+        );
+        assert_eq!((pressure.count, pressure.bytes), (2, 2 * 4 * 4));
+
+        let step = find(&report, "/instrument_step", Operation::Read);
+        assert_eq!((step.count, step.bytes), (2, 2 * 2));
+
+        assert!(report
+            .to_string()
+            .contains("/instrument_nested/instrument_pressure"));
+    }
+
+    #[derive(ContainerWrite)]
+    #[hdf5(mutate_on_write)]
+    struct InstrumentMismatch {
+        instrument_mismatch: Array2<f64>,
+    }
+
+    #[test]
+    fn failed_writes_not_recorded() {
+        let file = crate::memory::create_in_memory().unwrap();
+        file.new_dataset::<f64>()
+            .shape((2, 2))
+            .create("instrument_mismatch")
+            .unwrap();
+
+        let mismatch = InstrumentMismatch {
+            instrument_mismatch: Array2::zeros((3, 3)),
+        };
+        assert!(mismatch.write_hdf5(&file).is_err());
+
+        let report = io_report();
+        assert!(!report
+            .entries
+            .iter()
+            .any(|entry| entry.path == "/instrument_mismatch"));
+    }
+}
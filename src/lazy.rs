@@ -0,0 +1,341 @@
+use std::sync::OnceLock;
+
+use hdf5::Group;
+use ndarray::Slice;
+
+use crate::Error;
+use crate::ReadGroup;
+use crate::WriteGroup;
+
+/// A field that is only read from the HDF5 file the first time it is accessed.
+///
+/// When a container holding a `Lazy<T>` is read, only a handle to the parent
+/// [`hdf5::Group`] is stored. The first call to [`Lazy::get`] (or [`Lazy::get_mut`],
+/// [`Lazy::into_inner`]) reads `T` with the same routine that would have been used for
+/// a plain `T` field, so `T` can be a nested [`crate::ContainerRead`] group, an array,
+/// or a scalar attribute. Since nothing is read up front, errors such as a missing
+/// group or dataset are returned when the value is first accessed, not from `read_hdf5`.
+///
+/// This is useful for files with many groups where only a few are needed:
+///
+/// ```
+/// use hdf5_derive::{ContainerRead, ContainerWrite, Lazy};
+/// use ndarray::Array2;
+///
+/// #[derive(ContainerRead, ContainerWrite)]
+/// struct Diagnostics {
+///     energy: Array2<f64>,
+/// }
+///
+/// #[derive(ContainerRead, ContainerWrite)]
+/// struct Solution {
+///     timestep: u64,
+///     diagnostics: Lazy<Diagnostics>,
+/// }
+///
+/// let path = "./lazy_group.h5";
+/// let file = hdf5_derive::File::create(path).unwrap();
+///
+/// let solution = Solution {
+///     timestep: 100,
+///     diagnostics: Lazy::new(Diagnostics { energy: Array2::zeros((4, 4)) }),
+/// };
+/// solution.write_hdf5(&file).unwrap();
+///
+/// // `diagnostics` is not read here
+/// let loaded = Solution::read_hdf5(&file).unwrap();
+/// assert!(!loaded.diagnostics.is_loaded());
+///
+/// // the group is read on first access
+/// let diagnostics = loaded.diagnostics.get().unwrap();
+/// assert_eq!(diagnostics.energy, Array2::<f64>::zeros((4, 4)));
+///
+/// std::fs::remove_file(path).unwrap();
+/// ```
+///
+/// ## Macro Attributes
+///
+/// `transpose` and `rename` attributes on a `Lazy<T>` field are used when `T` is
+/// eventually read, exactly as if the field were a plain `T`. The same goes for the selection
+/// passed to [`crate::ContainerRead::read_hdf5_selection`]. Writing a `Lazy<T>`
+/// that has not been loaded yet will first read it from its original location.
+pub struct Lazy<T> {
+    source: Option<LazySource>,
+    value: OnceLock<T>,
+}
+
+/// where the value of a [`Lazy`] can be read from
+struct LazySource {
+    group: Group,
+    name: String,
+    transpose: bool,
+    selection: Option<Slice>,
+}
+
+impl<T> Lazy<T> {
+    /// construct a `Lazy` that already holds a value, i.e. for writing to a file
+    pub fn new(value: T) -> Self {
+        Self {
+            source: None,
+            value: OnceLock::from(value),
+        }
+    }
+
+    /// whether or not the value has been read from the file (or was provided with [`Lazy::new`])
+    pub fn is_loaded(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+impl<T> Lazy<T>
+where
+    T: ReadGroup,
+{
+    /// fetch the value, reading it from the file if this is the first access
+    ///
+    /// If reading fails, the error is returned and the next call will try to read
+    /// the value again.
+    pub fn get(&self) -> Result<&T, Error> {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+
+        let value = self.load()?;
+
+        Ok(self.value.get_or_init(|| value))
+    }
+
+    /// mutably fetch the value, reading it from the file if this is the first access
+    pub fn get_mut(&mut self) -> Result<&mut T, Error> {
+        if self.value.get().is_none() {
+            let value = self.load()?;
+            self.value.set(value).ok();
+        }
+
+        Ok(self
+            .value
+            .get_mut()
+            .expect("lazy value was initialized above"))
+    }
+
+    /// consume the `Lazy`, reading the value from the file if it has not been accessed yet
+    pub fn into_inner(self) -> Result<T, Error> {
+        match self.value.into_inner() {
+            Some(value) => Ok(value),
+            None => Self::read_source(&self.source),
+        }
+    }
+
+    fn load(&self) -> Result<T, Error> {
+        Self::read_source(&self.source)
+    }
+
+    fn read_source(source: &Option<LazySource>) -> Result<T, Error> {
+        let source = source
+            .as_ref()
+            .expect("a `Lazy` is either constructed with a value or read from a group");
+
+        match source.selection {
+            Some(selection) => {
+                T::read_group_selection(&source.group, &source.name, source.transpose, selection)
+            }
+            None => T::read_group(&source.group, &source.name, source.transpose),
+        }
+    }
+}
+
+impl<T> From<T> for Lazy<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> ReadGroup for Lazy<T>
+where
+    T: ReadGroup,
+{
+    fn read_group(group: &Group, array_name: &str, transpose: bool) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let source = LazySource {
+            group: group.clone(),
+            name: array_name.into(),
+            transpose,
+            selection: None,
+        };
+
+        Ok(Self {
+            source: Some(source),
+            value: OnceLock::new(),
+        })
+    }
+
+    fn read_group_selection(
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        selection: Slice,
+    ) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let source = LazySource {
+            group: group.clone(),
+            name: array_name.into(),
+            transpose,
+            selection: Some(selection),
+        };
+
+        Ok(Self {
+            source: Some(source),
+            value: OnceLock::new(),
+        })
+    }
+
+    /// checks `T` as if the field were a plain `T`, since that is what it is eventually read as
+    fn validate_group(
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        report: &mut crate::validate::ValidationReport,
+    ) where
+        Self: Sized,
+    {
+        T::validate_group(group, array_name, transpose, report)
+    }
+}
+
+impl<T> crate::schema::FieldLayout for Lazy<T>
+where
+    T: crate::schema::FieldLayout,
+{
+    fn layout() -> crate::schema::Layout {
+        crate::schema::Layout {
+            lazy: true,
+            ..T::layout()
+        }
+    }
+}
+
+impl<T> crate::LazyTwin for Lazy<T>
+where
+    T: ReadGroup,
+{
+    type Lazy = Self;
+
+    fn from_lazy(lazy: Self::Lazy, _transpose: bool) -> Result<Self, Error> {
+        Ok(lazy)
+    }
+}
+
+impl<T: crate::metadata::StoredAsObject> crate::metadata::StoredAsObject for Lazy<T> {}
+
+impl<T> WriteGroup for Lazy<T>
+where
+    T: ReadGroup + WriteGroup,
+{
+    fn write_group(
+        &self,
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        mutate_on_write: bool,
+    ) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        self.get()?
+            .write_group(group, array_name, transpose, mutate_on_write)
+    }
+
+    fn write_group_selection(
+        &self,
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        mutate_on_write: bool,
+        offset: usize,
+    ) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        self.get()?
+            .write_group_selection(group, array_name, transpose, mutate_on_write, offset)
+    }
+
+    fn append_group(&self, group: &Group, array_name: &str, transpose: bool) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        self.get()?.append_group(group, array_name, transpose)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContainerRead, ContainerWrite};
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::Array2;
+
+    #[derive(ContainerRead, ContainerWrite)]
+    struct Nested {
+        arr: Array2<u32>,
+    }
+
+    #[derive(ContainerRead, ContainerWrite)]
+    struct Outer {
+        scalar: Lazy<u64>,
+        arr: Lazy<Array2<u32>>,
+        nested: Lazy<Nested>,
+    }
+
+    #[test]
+    fn read_on_access() {
+        let path = "./lazy_read_on_access.h5";
+        let file = crate::File::create(path).unwrap();
+        let mut arr = Array2::zeros((3, 4));
+        arr[[1, 2]] = 5;
+
+        let outer = Outer {
+            scalar: Lazy::new(10),
+            arr: Lazy::new(arr.clone()),
+            nested: Lazy::new(Nested {
+                arr: arr.t().to_owned(),
+            }),
+        };
+        outer.write_hdf5(&file).unwrap();
+        file.close().unwrap();
+
+        let file = crate::File::open(path).unwrap();
+        let mut read = Outer::read_hdf5(&file).unwrap();
+
+        assert!(!read.scalar.is_loaded());
+        assert!(!read.arr.is_loaded());
+        assert!(!read.nested.is_loaded());
+
+        assert_eq!(*read.scalar.get().unwrap(), 10);
+        assert_eq!(*read.arr.get().unwrap(), arr);
+        assert!(read.scalar.is_loaded());
+        assert!(!read.nested.is_loaded());
+
+        read.nested.get_mut().unwrap().arr[[0, 0]] = 1;
+        assert_eq!(read.nested.into_inner().unwrap().arr[[0, 0]], 1);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn missing_group_errors_on_access() {
+        let path = "./lazy_missing_group.h5";
+        let file = crate::File::create(path).unwrap();
+
+        let lazy: Lazy<Nested> = ReadGroup::read_group(&file, "missing", false).unwrap();
+        assert!(lazy.get().is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,14 +1,25 @@
 use std::marker::PhantomData;
+use std::sync::OnceLock;
 
+use hdf5::dataset::Layout;
+use hdf5::plist::file_access::FileDriver;
 use hdf5::Dataset;
 use hdf5::H5Type;
 
+use memmap2::Mmap;
+use memmap2::MmapOptions;
+
 use ndarray::Array;
+use ndarray::ArrayD;
 use ndarray::ArrayView;
+use ndarray::Axis;
 use ndarray::Dimension;
+use ndarray::Slice;
 use ndarray::SliceArg;
 
 use crate::error;
+use crate::progress;
+use crate::progress::Operation;
 use crate::Error;
 
 /// one dimensional lazy array backed by HDF5 dataset
@@ -36,19 +47,25 @@ pub type LazyArray6<T> = LazyArray<T, ndarray::Ix6>;
 ///
 /// ## Macro Attributes
 ///
-/// `LazyArray` ignores `transpose` and `mutate_on_write` attributes.
+/// `LazyArray` ignores `mutate_on_write` attributes. It also ignores the selection
+/// passed to [`crate::ContainerRead::read_hdf5_selection`], the whole dataset is always available.
 /// [`crate::ContainerWrite`]'s write routines have no effect on a `LazyArray`, all data is written
 /// immediately to the underlying datasets.
 ///
-/// `transpose` may have some effect in the future if required. For now, the following is ignored:
+/// A `LazyArray` cannot be transposed. Reading or writing a `LazyArray` field that is marked with
+/// `transpose` fails with [`crate::error::TransposeUnsupported`]. If the container is transposed,
+/// opt the field out:
 ///
 /// ```
 /// use hdf5_derive::ContainerRead;
 /// use hdf5_derive::LazyArray5;
+/// use ndarray::Array2;
 ///
 /// #[derive(ContainerRead)]
+/// #[hdf5(transpose="both")]
 /// struct Container {
-///     #[hdf5(transpose="both")]
+///     small_array: Array2<f64>,
+///     #[hdf5(transpose="none")]
 ///     large_array: LazyArray5<u64>
 /// }
 /// ```
@@ -65,6 +82,7 @@ where
 {
     dataset: Dataset,
     name: String,
+    mmap: OnceLock<Mmap>,
     _numeric_type: PhantomData<T>,
     _dimension: PhantomData<DIM>,
 }
@@ -104,7 +122,7 @@ where
 
         // check the dimensions are correct
         if ndim != dataset.ndim() {
-            return Err(error::DimensionMismatch::new(&name, ndim, dataset.ndim()).into());
+            return Err(error::DimensionMismatch::new(&name, dataset.ndim(), ndim).into());
         }
 
         // check the datatypes are correct
@@ -113,12 +131,13 @@ where
             .map_err(|e| error::MissingDatatype::from_field_name(&name, e))?;
 
         if !dtype.is::<T>() {
-            return Err(error::WrongDatatype::new(&name, dtype).into());
+            return Err(error::WrongDatatype::new(&name, T::type_descriptor(), dtype).into());
         }
 
         let ret = Self {
             dataset,
             name,
+            mmap: OnceLock::new(),
             _numeric_type: PhantomData,
             _dimension: PhantomData,
         };
@@ -237,10 +256,294 @@ where
         Ok(())
     }
 
+    /// read the entire dataset in blocks of `chunk_len` elements along the leading axis
+    ///
+    /// Each block is reported to the observer installed with [`crate::progress::observe`], which can
+    /// stop the read between blocks.
+    pub fn read_chunked(&self, chunk_len: usize) -> Result<Array<T, DIM>, crate::Error> {
+        let shape = self.dataset.shape();
+        // a scalar dataset is read in a single block
+        let total = shape.first().copied().unwrap_or(1);
+        let chunk_len = chunk_len.max(1);
+
+        let mut tracker = progress::ChunkTracker::new(Operation::Read, &self.name, total);
+        let mut data = Vec::with_capacity(shape.iter().product());
+
+        let mut start = 0;
+        while start < total {
+            tracker.check()?;
+
+            let end = (start + chunk_len).min(total);
+
+            let block: ArrayD<T> = if shape.is_empty() {
+                self.dataset.read_dyn()
+            } else {
+                self.dataset.read_slice(crate::axis_selection(
+                    shape.len(),
+                    0,
+                    Slice::from(start..end),
+                ))
+            }
+            .map_err(|e| error::ReadSlice::from_field_name(&self.name, e))?;
+
+            let bytes = block.len() * std::mem::size_of::<T>();
+            // blocks read from hdf5 are in standard layout, so their elements follow each other in the full array
+            data.extend(block.into_raw_vec());
+
+            tracker.advance(end, bytes);
+            start = end;
+        }
+
+        ArrayD::from_shape_vec(shape, data)
+            .and_then(|array| array.into_dimensionality::<DIM>())
+            .map_err(|e| error::ReadSlice::from_field_name(&self.name, e.into()).into())
+    }
+
+    /// write `array` to the entire dataset in blocks of `chunk_len` elements along the leading axis
+    ///
+    /// Each block is reported to the observer installed with [`crate::progress::observe`], which can
+    /// stop the write between blocks. Blocks written before the write is stopped stay in the dataset.
+    pub fn write_chunked(
+        &self,
+        array: ArrayView<'_, T, DIM>,
+        chunk_len: usize,
+    ) -> Result<(), crate::Error>
+    where
+        T: Clone,
+    {
+        // hdf5 can only write slices from standard layout arrays
+        let array = array.as_standard_layout().into_dyn();
+        let total = array.shape().first().copied().unwrap_or(1);
+        let chunk_len = chunk_len.max(1);
+
+        let mut tracker = progress::ChunkTracker::new(Operation::Write, &self.name, total);
+
+        let mut start = 0;
+        while start < total {
+            tracker.check()?;
+
+            let end = (start + chunk_len).min(total);
+
+            let block = if array.ndim() == 0 {
+                array.view()
+            } else {
+                array.slice_axis(Axis(0), Slice::from(start..end))
+            };
+
+            let bytes = block.len() * std::mem::size_of::<T>();
+            let info = crate::axis_selection(array.ndim(), 0, Slice::from(start..end));
+
+            self.dataset
+                .write_slice(block, info)
+                .map_err(|e| error::WriteSlice::from_field_name(&self.name, e))?;
+
+            tracker.advance(end, bytes);
+            start = end;
+        }
+
+        Ok(())
+    }
+
+    /// view the entire dataset as an [`ArrayView`] backed by a memory map of the HDF5 file
+    ///
+    /// No data is read up front: the operating system pages in regions of the file as the view
+    /// is accessed. This is only possible when the file is opened with the default `sec2` driver, and
+    /// the dataset is stored contiguously on disk without any filters (compression, shuffling, etc.)
+    /// with storage already allocated.
+    /// Since the type of the `LazyArray` is checked against the dataset on construction, the data is
+    /// guaranteed to be in the native byte order. If the dataset layout does not allow mapping,
+    /// [`Error::MmapUnsupported`] is returned.
+    ///
+    /// The map is created on the first call and reused afterwards. The view reflects the
+    /// contents of the file on disk, so any writes made through `hdf5` should be flushed
+    /// (or the file closed) before mapping. Truncating or otherwise modifying the file from
+    /// another process while the view is alive is not supported.
+    ///
+    /// ## Exmaple
+    ///
+    /// ```
+    /// use hdf5_derive::{LazyArray3, ContainerRead, ContainerWrite};
+    /// let path = "./mmap_file.h5";
+    /// let file = hdf5_derive::File::create(path).unwrap();
+    ///
+    /// #[derive(ContainerWrite)]
+    /// struct WriteHelper {
+    ///     arr: ndarray::Array3<f64>
+    /// }
+    ///
+    /// let mut arr = ndarray::Array3::zeros((4,4,4));
+    /// arr[[1,2,3]] = 10.;
+    ///
+    /// let helper = WriteHelper { arr: arr.clone() };
+    /// helper.write_hdf5(&file).unwrap();
+    /// file.close();
+    ///
+    /// #[derive(ContainerRead)]
+    /// struct LazyReader {
+    ///     arr: LazyArray3<f64>
+    /// }
+    ///
+    /// let file = hdf5_derive::File::open(path).unwrap();
+    /// let lazy_reader = LazyReader::read_hdf5(&file).unwrap();
+    ///
+    /// let view = lazy_reader.arr.mmap_view().unwrap();
+    /// assert_eq!(view, arr);
+    ///
+    /// std::fs::remove_file(path);
+    /// ```
+    pub fn mmap_view(&self) -> Result<ArrayView<'_, T, DIM>, crate::Error> {
+        let mmap = match self.mmap.get() {
+            Some(mmap) => mmap,
+            None => {
+                let mmap = self.map_dataset()?;
+                self.mmap.get_or_init(|| mmap)
+            }
+        };
+
+        let shape = self.dataset.shape();
+        let num_elements: usize = shape.iter().product();
+
+        let mut dim = DIM::zeros(shape.len());
+        dim.slice_mut().copy_from_slice(&shape);
+
+        // SAFETY: `map_dataset` ensures that the map spans exactly `num_elements` values of `T`,
+        // that the start of the map is aligned for `T`, and the dtype check in `new` ensures
+        // that the bytes on disk have the same representation as `T`
+        let data = unsafe { std::slice::from_raw_parts(mmap.as_ptr() as *const T, num_elements) };
+
+        let view = ArrayView::from_shape(dim, data)
+            .expect("memory map length matches the shape of the dataset");
+
+        Ok(view)
+    }
+
+    /// check that the dataset layout can be memory mapped, and map the region of
+    /// the file that contains the raw data
+    fn map_dataset(&self) -> Result<Mmap, crate::Error> {
+        // offsets are only positions in a single file on disk with the default `sec2` driver. The
+        // `core` driver keeps the file in memory, and `family` / `multi` split it across files
+        let driver = self
+            .dataset
+            .file()
+            .and_then(|file| file.fapl())
+            .map_err(|e| {
+                error::MmapUnsupported::new(&self.name, format!("file driver is unknown: {e}"))
+            })?
+            .driver();
+
+        if !matches!(driver, FileDriver::Sec2) {
+            let reason = format!("file is opened with the {:?} driver, not sec2", driver);
+            return Err(error::MmapUnsupported::new(&self.name, reason).into());
+        }
+
+        let layout = self.dataset.layout();
+        if layout != Layout::Contiguous {
+            let reason = format!("dataset layout is {:?}, not contiguous", layout);
+            return Err(error::MmapUnsupported::new(&self.name, reason).into());
+        }
+
+        if !self.dataset.filters().is_empty() {
+            let reason = "dataset has filters (compression, shuffling, etc.) applied";
+            return Err(error::MmapUnsupported::new(&self.name, reason).into());
+        }
+
+        let offset = self.dataset.offset().ok_or_else(|| {
+            error::MmapUnsupported::new(
+                &self.name,
+                "storage for the dataset has not been allocated",
+            )
+        })?;
+
+        if offset % std::mem::align_of::<T>() as u64 != 0 {
+            let reason = format!(
+                "dataset offset {} is not aligned for the element type",
+                offset
+            );
+            return Err(error::MmapUnsupported::new(&self.name, reason).into());
+        }
+
+        let num_bytes = self.dataset.size() * std::mem::size_of::<T>();
+
+        if num_bytes == 0 {
+            return Err(
+                error::MmapUnsupported::new(&self.name, "dataset contains no elements").into(),
+            );
+        }
+
+        let file_name = self.dataset.filename();
+
+        let file = std::fs::File::open(&file_name)
+            .map_err(|e| error::MapFile::new(&self.name, &file_name, e))?;
+
+        // SAFETY: the map is read-only. Other processes truncating the file while the map
+        // is alive is documented as unsupported in `mmap_view`
+        let mmap = unsafe { MmapOptions::new().offset(offset).len(num_bytes).map(&file) }
+            .map_err(|e| error::MapFile::new(&self.name, &file_name, e))?;
+
+        Ok(mmap)
+    }
+
     /// provides access to the underlying dataset
     pub fn dataset(&self) -> &hdf5::Dataset {
         &self.dataset
     }
+
+    /// read a slice of the dataset on the tokio blocking thread pool. See [`LazyArray::slice`] and
+    /// [`crate::async_io`]
+    #[cfg(feature = "async")]
+    pub async fn slice_async<I>(&self, info: I) -> Result<Array<T, I::OutDim>, crate::Error>
+    where
+        T: Send + 'static,
+        I: SliceArg<DIM> + TryInto<hdf5::Selection> + Send + 'static,
+        I::OutDim: 'static,
+        hdf5::Error: From<I::Error>,
+    {
+        let dataset = self.dataset.clone();
+        let name = self.name.clone();
+
+        crate::async_io::blocking(move || {
+            dataset
+                .read_slice(info)
+                .map_err(|e| error::ReadSlice::from_field_name(&name, e).into())
+        })
+        .await
+    }
+
+    /// write an array to a slice of the dataset on the tokio blocking thread pool. See
+    /// [`LazyArray::write_slice`] and [`crate::async_io`]
+    #[cfg(feature = "async")]
+    pub async fn write_slice_async<I>(
+        &self,
+        array: Array<T, I::OutDim>,
+        info: I,
+    ) -> Result<(), crate::Error>
+    where
+        T: Send + 'static,
+        I: SliceArg<DIM> + TryInto<hdf5::Selection> + Send + 'static,
+        I::OutDim: 'static,
+        hdf5::Error: From<I::Error>,
+    {
+        let dataset = self.dataset.clone();
+        let name = self.name.clone();
+
+        crate::async_io::blocking(move || {
+            dataset
+                .write_slice(array.view(), info)
+                .map_err(|e| error::WriteSlice::from_field_name(&name, e).into())
+        })
+        .await
+    }
+
+    /// reload the metadata of the dataset from a file opened with [`crate::swmr::SwmrReader`], so that
+    /// data appended by the writer since the `LazyArray` was constructed (and the new shape of the
+    /// dataset) is visible. Any memory map from [`LazyArray::mmap_view`] is discarded.
+    #[cfg(feature = "swmr")]
+    pub fn refresh(&mut self) -> Result<(), crate::Error> {
+        crate::swmr::refresh(&self.dataset, &self.name)?;
+        self.mmap = OnceLock::new();
+
+        Ok(())
+    }
 }
 
 impl<T, DIM> std::ops::Deref for LazyArray<T, DIM>
@@ -265,17 +568,68 @@ where
         Self: Sized,
     {
         if transpose {
-            panic!("transposing not supported for LazyArray s");
+            return Err(error::TransposeUnsupported::new(array_name).into());
         }
 
         let ds = group
             .dataset(array_name)
             .map_err(|e| error::MissingDataset::from_field_name(array_name, e))?;
 
+        #[cfg(feature = "swmr")]
+        if crate::swmr::mode() == Some(crate::swmr::Mode::Refresh) {
+            crate::swmr::refresh(&ds, array_name)?;
+        }
+
         let ret = Self::new(ds)?;
 
         Ok(ret)
     }
+
+    fn validate_group(
+        group: &hdf5::Group,
+        array_name: &str,
+        transpose: bool,
+        report: &mut crate::validate::ValidationReport,
+    ) where
+        Self: Sized,
+    {
+        if transpose {
+            report.push_problem(error::TransposeUnsupported::new(array_name));
+        }
+
+        let ds = match crate::validate::dataset(group, array_name, report) {
+            Some(ds) => ds,
+            None => return,
+        };
+
+        // slices are read without conversion, so the datatype must match exactly
+        let datatype = match ds.dtype() {
+            Ok(dtype) if dtype.is::<T>() => Ok(()),
+            Ok(dtype) => {
+                Err(error::WrongDatatype::new(array_name, T::type_descriptor(), dtype).into())
+            }
+            Err(e) => Err(error::MissingDatatype::from_field_name(array_name, e).into()),
+        };
+
+        let checks = [crate::check_dimension::<DIM>(&ds, array_name), datatype];
+
+        if report.push_results(checks) && !transpose {
+            report.push_checked(
+                group,
+                array_name,
+                crate::validate::ObjectKind::Dataset,
+                &ds.shape(),
+                false,
+            );
+        }
+    }
+}
+
+impl<T, DIM> crate::metadata::StoredAsObject for LazyArray<T, DIM>
+where
+    DIM: Dimension,
+    T: H5Type,
+{
 }
 
 impl<T, DIM> crate::WriteGroup for LazyArray<T, DIM>
@@ -286,7 +640,7 @@ where
     fn write_group(
         &self,
         _group: &hdf5::Group,
-        _array_name: &str,
+        array_name: &str,
         transpose: bool,
         _mutate_on_write: bool,
     ) -> Result<(), Error>
@@ -294,12 +648,78 @@ where
         Self: Sized,
     {
         if transpose {
-            panic!("transpose not supported for writing lazy arrays");
+            return Err(error::TransposeUnsupported::new(array_name).into());
         }
 
         // we do not need to do anything, all writes are already backed by the lazy array
         Ok(())
     }
+
+    fn append_group(
+        &self,
+        _group: &hdf5::Group,
+        array_name: &str,
+        transpose: bool,
+    ) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        if transpose {
+            return Err(error::TransposeUnsupported::new(array_name).into());
+        }
+
+        // as for `write_group`, the data is already in the underlying dataset
+        Ok(())
+    }
+}
+
+impl<T, DIM> crate::schema::FieldLayout for LazyArray<T, DIM>
+where
+    DIM: Dimension,
+    T: H5Type,
+{
+    fn layout() -> crate::schema::Layout {
+        crate::schema::Layout {
+            lazy: true,
+            ..crate::schema::array_layout::<T, DIM>()
+        }
+    }
+}
+
+impl<T, DIM> crate::LazyTwin for LazyArray<T, DIM>
+where
+    DIM: Dimension,
+    T: H5Type,
+{
+    type Lazy = Self;
+
+    fn from_lazy(lazy: Self::Lazy, _transpose: bool) -> Result<Self, Error> {
+        Ok(lazy)
+    }
+}
+
+impl<T, DIM> crate::LazyTwin for Array<T, DIM>
+where
+    DIM: Dimension,
+    T: H5Type,
+{
+    type Lazy = LazyArray<T, DIM>;
+
+    fn from_lazy(lazy: Self::Lazy, transpose: bool) -> Result<Self, Error> {
+        let array: Self = lazy
+            .dataset
+            .read()
+            .map_err(|e| error::SerializeArray::from_field_name(&lazy.name, e))?;
+
+        // handle transposing the array
+        let array = if transpose {
+            array.reversed_axes()
+        } else {
+            array
+        };
+
+        Ok(array)
+    }
 }
 
 #[cfg(test)]
@@ -394,4 +814,87 @@ mod tests {
 
         std::fs::remove_file(path).unwrap();
     }
+
+    #[test]
+    fn mmap_view() {
+        let path = "./lazy_mmap_view.h5";
+        let file = crate::File::create(path).unwrap();
+        let mut data = ndarray::Array3::<f32>::zeros((5, 4, 3));
+        data[[1, 2, 1]] = 10.;
+        data[[4, 3, 2]] = 15.;
+
+        let helper = Helper { dim3: data.clone() };
+        helper.write_hdf5(&file).unwrap();
+        file.close().unwrap();
+
+        let file = crate::File::open(path).unwrap();
+        let lazy_reader = LazyTest::read_hdf5(&file).unwrap();
+
+        let view = lazy_reader.dim3.mmap_view().unwrap();
+        assert_eq!(view, data);
+
+        // the second call reuses the existing map
+        let view = lazy_reader.dim3.mmap_view().unwrap();
+        assert_eq!(view[[4, 3, 2]], 15.);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn mmap_view_chunked() {
+        let path = "./lazy_mmap_view_chunked.h5";
+        let file = crate::File::create(path).unwrap();
+
+        let dataset = file
+            .new_dataset::<f32>()
+            .shape((5, 5, 5))
+            .chunk((1, 5, 5))
+            .create("dim3")
+            .unwrap();
+
+        let lazy_array = LazyArray3::<f32>::new(dataset).unwrap();
+        let res = lazy_array.mmap_view();
+
+        assert!(matches!(res, Err(Error::MmapUnsupported(_))));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn mmap_view_in_memory() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        let helper = Helper {
+            dim3: ndarray::Array3::zeros((2, 2, 2)),
+        };
+        helper.write_hdf5(&file).unwrap();
+
+        let lazy_reader = LazyTest::read_hdf5(&file).unwrap();
+        let res = lazy_reader.dim3.mmap_view();
+
+        assert!(matches!(res, Err(Error::MmapUnsupported(_))));
+    }
+
+    #[test]
+    fn transpose_rejected() {
+        #[derive(ContainerRead)]
+        #[allow(dead_code)]
+        struct Transposed {
+            #[hdf5(transpose = "read")]
+            dim3: LazyArray3<f32>,
+        }
+
+        let file = crate::memory::create_in_memory().unwrap();
+
+        let helper = Helper {
+            dim3: ndarray::Array3::zeros((2, 2, 2)),
+        };
+        helper.write_hdf5(&file).unwrap();
+
+        let res = Transposed::read_hdf5(&file);
+        assert!(matches!(res, Err(Error::TransposeUnsupported(_))));
+
+        let report = Transposed::validate_hdf5(&file);
+        assert!(!report.is_valid());
+    }
 }
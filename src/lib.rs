@@ -1,20 +1,54 @@
 #![doc = include_str!("../README.md")]
 #![warn(missing_docs)]
 
-pub use macros::{ContainerRead, ContainerWrite};
+pub use macros::{ContainerAttributes, ContainerRead, ContainerSchema, ContainerWrite};
 
 pub use hdf5::File;
 pub use hdf5::Group;
+pub use hdf5::Location;
+pub use ndarray::Slice;
 
+#[cfg(feature = "async")]
+pub mod async_io;
+mod async_writer;
+mod cancel;
+mod checkpoint;
 pub mod error;
+mod file_io;
+#[cfg(feature = "tracing")]
+pub mod instrument;
+mod lazy;
 mod lazy_array;
+pub mod memory;
+pub mod metadata;
+#[cfg(feature = "mpi")]
+pub mod parallel;
+pub mod progress;
+#[cfg(feature = "uom")]
+pub mod quantity;
+pub mod schema;
+mod series;
+#[cfg(feature = "swmr")]
+pub mod swmr;
+pub mod validate;
+mod with_attrs;
+pub mod write_complete;
 
+pub use async_writer::*;
+pub use cancel::CancellationToken;
+pub use checkpoint::*;
+pub use file_io::WriteMode;
+pub use lazy::*;
 pub use lazy_array::*;
+pub use schema::ContainerSchema;
+pub use series::*;
+pub use with_attrs::*;
 
 #[doc(hidden)]
 pub use error::*;
 
 use num_traits::Zero;
+use std::path::Path;
 
 /// Provides methods for writing a struct's contents to a file. Derived with [`ContainerWrite`]
 /// proc macro.
@@ -50,6 +84,115 @@ pub trait ContainerWrite {
     /// std::fs::remove_file(path).unwrap();
     /// ```
     fn write_hdf5(&self, container: &Group) -> Result<(), Error>;
+
+    /// write each array of a struct into a region of an existing, larger dataset, starting at
+    /// `offset` along the leading axis
+    ///
+    /// This is the counterpart to [`ContainerRead::read_hdf5_selection`]: when each process of a
+    /// domain decomposition holds a block of a global field, the blocks can be assembled into
+    /// preallocated global datasets. All datasets and nested groups must already exist.
+    /// Scalar attributes are created by the first block and overwritten by the others. Fields marked
+    /// with `#[hdf5(select = false)]` are written with [`ContainerWrite::write_hdf5`] as normal.
+    ///
+    /// A single block does not complete the container, so the marker of `#[hdf5(write_complete)]`
+    /// containers is left untouched. Call [`write_complete::clear`] before writing the first block and
+    /// [`write_complete::mark`] once every block has been written.
+    ///
+    /// ```
+    /// use hdf5_derive::ContainerWrite;
+    /// use ndarray::{Array2, s};
+    ///
+    /// #[derive(ContainerWrite)]
+    /// struct Block {
+    ///     pressure: Array2<f64>
+    /// }
+    ///
+    /// let path = "./test_file_write_selection.h5";
+    /// let file = hdf5_derive::File::create(path).unwrap();
+    ///
+    /// // preallocate the global dataset
+    /// file.new_dataset::<f64>()
+    ///     .shape((10, 4))
+    ///     .create("pressure")
+    ///     .unwrap();
+    ///
+    /// // write rows 6 through 9
+    /// let block = Block { pressure: Array2::ones((4, 4)) };
+    /// block.write_hdf5_selection(&file, 6).unwrap();
+    ///
+    /// let global: Array2<f64> = file.dataset("pressure").unwrap().read().unwrap();
+    /// assert_eq!(global.slice(s![6.., ..]), block.pressure);
+    /// assert_eq!(global.slice(s![..6, ..]), Array2::<f64>::zeros((6, 4)));
+    ///
+    /// std::fs::remove_file(path).unwrap();
+    /// ```
+    ///
+    /// Hand-written implementations that do not override this method return
+    /// [`error::OperationUnsupported`].
+    fn write_hdf5_selection(&self, _container: &Group, _offset: usize) -> Result<(), Error> {
+        Err(
+            error::OperationUnsupported::new(std::any::type_name::<Self>(), "write_hdf5_selection")
+                .into(),
+        )
+    }
+
+    /// append each array of a struct to the end of its existing, resizable dataset along the
+    /// leading axis. Used by `swmr::SwmrWriter::append`, which requires the `swmr` feature
+    ///
+    /// No objects or attributes are created. Scalar attributes, fields marked with
+    /// `#[hdf5(select = false)]` and the attributes describing fields are left as they were first
+    /// written.
+    ///
+    /// Hand-written implementations that do not override this method return
+    /// [`error::OperationUnsupported`].
+    fn append_hdf5(&self, _container: &Group) -> Result<(), Error> {
+        Err(error::OperationUnsupported::new(std::any::type_name::<Self>(), "append_hdf5").into())
+    }
+
+    /// open the file at `path` according to `mode`, and write the contents of a struct to its root group
+    ///
+    /// Errors from opening, closing or renaming the file include `path`.
+    ///
+    /// ```
+    /// use hdf5_derive::{ContainerRead, ContainerWrite, WriteMode};
+    /// use ndarray::Array2;
+    ///
+    /// #[derive(ContainerRead, ContainerWrite)]
+    /// struct Data {
+    ///     some_field: Array2<u32>
+    /// }
+    ///
+    /// let path = "./test_file_write_to_path.h5";
+    /// let data = Data { some_field: Array2::zeros((5, 5)) };
+    ///
+    /// // readers of `path` never see a partially written file
+    /// data.write_hdf5_to_path(path, WriteMode::AtomicReplace).unwrap();
+    ///
+    /// let read_data = Data::read_hdf5_from_path(path).unwrap();
+    /// assert_eq!(read_data.some_field, data.some_field);
+    ///
+    /// std::fs::remove_file(path).unwrap();
+    /// ```
+    fn write_hdf5_to_path<P: AsRef<Path>>(&self, path: P, mode: WriteMode) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        file_io::write_to_path(self, path.as_ref(), mode)
+    }
+
+    /// write the contents of a struct to a new in-memory file, and return its file image
+    ///
+    /// The image can be read back with [`ContainerRead::read_hdf5_from_image`], or written to
+    /// disk as a regular HDF5 file. See [`memory`] for an example.
+    fn write_hdf5_to_image(&self) -> Result<Vec<u8>, Error>
+    where
+        Self: Sized,
+    {
+        let file = memory::create_in_memory()?;
+        self.write_hdf5(&file)?;
+
+        memory::file_image(&file)
+    }
 }
 
 /// Provides methods for reading a struct's contents from a file. Derived with [`ContainerRead`]
@@ -90,6 +233,138 @@ pub trait ContainerRead {
     fn read_hdf5(container: &Group) -> Result<Self, Error>
     where
         Self: Sized;
+
+    /// read the contents of an HDF5 file to `Self`, only reading the part of each array
+    /// that lies in `selection` along its leading axis
+    ///
+    /// This is useful for domain decomposition, where each process only needs a
+    /// subdomain of a larger dataset. Scalar attributes are read as normal. Fields
+    /// marked with `#[hdf5(select = false)]` (or every field of a container marked with
+    /// `#[hdf5(select = false)]`) are read in full.
+    ///
+    /// ```
+    /// use hdf5_derive::{ContainerRead, ContainerWrite};
+    /// use ndarray::{Array1, Array2, Slice, s};
+    ///
+    /// #[derive(ContainerRead, ContainerWrite)]
+    /// struct Data {
+    ///     pressure: Array2<f64>,
+    ///     #[hdf5(select = false)]
+    ///     grid: Array1<f64>,
+    /// }
+    ///
+    /// let path = "./test_file_read_selection.h5";
+    /// let file = hdf5_derive::File::create(path).unwrap();
+    ///
+    /// let pressure = Array2::from_shape_fn((10, 4), |(i, j)| (i * 4 + j) as f64);
+    /// let grid = Array1::linspace(0., 1., 4);
+    /// let data = Data { pressure: pressure.clone(), grid: grid.clone() };
+    /// data.write_hdf5(&file).unwrap();
+    ///
+    /// // only read rows 2 through 5 of `pressure`
+    /// let subdomain = Data::read_hdf5_selection(&file, Slice::from(2..5)).unwrap();
+    ///
+    /// assert_eq!(subdomain.pressure, pressure.slice(s![2..5, ..]));
+    /// assert_eq!(subdomain.grid, grid);
+    ///
+    /// std::fs::remove_file(path).unwrap();
+    /// ```
+    ///
+    /// Hand-written implementations that do not override this method return
+    /// [`error::OperationUnsupported`].
+    fn read_hdf5_selection(_container: &Group, _selection: Slice) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Err(
+            error::OperationUnsupported::new(std::any::type_name::<Self>(), "read_hdf5_selection")
+                .into(),
+        )
+    }
+
+    /// read the contents of an HDF5 file to `Self` like [`ContainerRead::read_hdf5`], but attempt every
+    /// field (including the fields of nested containers) instead of stopping at the first error
+    ///
+    /// If any field fails, an [`Error::Aggregate`] listing every failure with its path is returned, so a file
+    /// with several missing or mismatched datasets can be fixed in one go.
+    ///
+    /// ```
+    /// use hdf5_derive::{ContainerRead, ContainerWrite, Error};
+    /// use ndarray::Array2;
+    ///
+    /// #[derive(ContainerRead, ContainerWrite)]
+    /// struct Nested {
+    ///     vorticity: Array2<f64>,
+    /// }
+    ///
+    /// #[derive(ContainerRead, ContainerWrite)]
+    /// struct Data {
+    ///     velocity: Array2<f64>,
+    ///     pressure: Array2<f64>,
+    ///     nested: Nested,
+    /// }
+    ///
+    /// let file = hdf5_derive::memory::create_in_memory().unwrap();
+    /// // only `pressure` exists
+    /// file.new_dataset::<f64>().shape((2, 2)).create("pressure").unwrap();
+    /// file.create_group("nested").unwrap();
+    ///
+    /// let error = Data::read_hdf5_report(&file).err().unwrap();
+    ///
+    /// if let Error::Aggregate(aggregate) = error {
+    ///     let paths: Vec<_> = aggregate.errors().iter().map(|e| e.path().unwrap()).collect();
+    ///     assert_eq!(paths, ["/velocity", "/nested/vorticity"]);
+    /// } else {
+    ///     panic!("expected every error");
+    /// }
+    /// ```
+    ///
+    /// Hand-written implementations that do not override this method stop at the first error, like
+    /// [`ContainerRead::read_hdf5`].
+    fn read_hdf5_report(container: &Group) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Self::read_hdf5(container)
+    }
+
+    /// check that every field of `Self` could be read from `container`, without reading any data
+    ///
+    /// Each dataset, attribute and group is checked for existence, the kind of object, its rank and its
+    /// datatype, and every problem found is collected in the report. See [`validate`] for an example.
+    ///
+    /// Hand-written implementations that do not override this method report the whole container in
+    /// [`validate::ValidationReport::unchecked`].
+    fn validate_hdf5(container: &Group) -> validate::ValidationReport
+    where
+        Self: Sized,
+    {
+        let mut report = validate::ValidationReport::default();
+        report.push_unchecked(container.name(), std::any::type_name::<Self>());
+        report
+    }
+
+    /// open the file at `path` read-only, and read `Self` from its root group
+    ///
+    /// Errors from opening the file include `path`. See [`ContainerWrite::write_hdf5_to_path`]
+    /// for an example.
+    fn read_hdf5_from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        file_io::read_from_path(path.as_ref())
+    }
+
+    /// open a file image (such as one from [`ContainerWrite::write_hdf5_to_image`]) in memory,
+    /// and read `Self` from its root group
+    fn read_hdf5_from_image(image: &[u8]) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let file = memory::open_image(image)?;
+
+        Self::read_hdf5(&file)
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -143,12 +418,247 @@ pub enum Error {
     /// Datatype for [`crate::LazyArray`] was incorrect
     #[error(transparent)]
     WrongDatatype(#[from] error::WrongDatatype),
+    /// Could not open or create an HDF5 file
+    #[error(transparent)]
+    OpenFile(#[from] error::OpenFile),
+    /// Could not flush and close an HDF5 file
+    #[error(transparent)]
+    CloseFile(#[from] error::CloseFile),
     /// Failed to read a slice of data from an HDF5 dataset
     #[error(transparent)]
     ReadSlice(#[from] error::ReadSlice),
     /// Failed to write a slice of data to an HDF5 dataset
     #[error(transparent)]
     WriteSlice(#[from] error::WriteSlice),
+    /// The dataset backing a [`crate::LazyArray`] cannot be memory mapped
+    #[error(transparent)]
+    MmapUnsupported(#[from] error::MmapUnsupported),
+    /// Failed to memory map the file backing a [`crate::LazyArray`]
+    #[error(transparent)]
+    MapFile(#[from] error::MapFile),
+    /// A filesystem operation on an HDF5 file or its directory failed
+    #[error(transparent)]
+    FileIo(#[from] error::FileIo),
+    /// A container with `#[hdf5(write_complete)]` was read from a group without the marker set
+    #[error(transparent)]
+    IncompleteWrite(#[from] error::IncompleteWrite),
+    /// Could not copy an HDF5 file to or from an in-memory file image
+    #[error(transparent)]
+    FileImage(#[from] error::FileImage),
+    /// Could not switch an HDF5 file to single-writer / multiple-reader mode
+    #[error(transparent)]
+    StartSwmr(#[from] error::StartSwmr),
+    /// Failed to flush a dataset written in single-writer / multiple-reader mode
+    #[error(transparent)]
+    FlushDataset(#[from] error::FlushDataset),
+    /// Failed to refresh a dataset read in single-writer / multiple-reader mode
+    #[error(transparent)]
+    RefreshDataset(#[from] error::RefreshDataset),
+    /// The thread of an [`AsyncWriter`] stopped unexpectedly
+    #[error(transparent)]
+    BackgroundWriter(#[from] error::BackgroundWriter),
+    /// A [`CancellationToken`] was cancelled part way through a read or write
+    #[error(transparent)]
+    Cancelled(#[from] error::Cancelled),
+    /// A blocking task spawned by one of the async wrappers failed
+    #[error(transparent)]
+    BlockingTask(#[from] error::BlockingTask),
+    /// An array written with `mutate_on_write` has a different shape than the existing dataset
+    #[error(transparent)]
+    ShapeMismatch(#[from] error::ShapeMismatch),
+    /// A field is stored as a different kind of object than its type expects
+    #[error(transparent)]
+    WrongObjectKind(#[from] error::WrongObjectKind),
+    /// A field marked with `transpose` has a type that cannot be transposed
+    #[error(transparent)]
+    TransposeUnsupported(#[from] error::TransposeUnsupported),
+    /// A hand-written container does not implement an optional method, such as
+    /// [`ContainerWrite::write_hdf5_selection`]
+    #[error(transparent)]
+    OperationUnsupported(#[from] error::OperationUnsupported),
+    /// Attributes were attached to a field that is itself stored as an attribute
+    #[error(transparent)]
+    AttributesUnsupported(#[from] error::AttributesUnsupported),
+    /// A field marked with `check_units` is stored in different units
+    #[error(transparent)]
+    UnitsMismatch(#[from] error::UnitsMismatch),
+    /// Several fields failed to read with [`ContainerRead::read_hdf5_report`]
+    #[error(transparent)]
+    Aggregate(#[from] error::Aggregate),
+}
+
+/// forwards the [`error::ErrorContext`] of each variant of [`Error`]
+macro_rules! error_context {
+    ($($variant:ident),+ $(,)?) => {
+        impl Error {
+            /// where in the file the error happened. See [`error::ErrorContext`]
+            pub fn context(&self) -> &error::ErrorContext {
+                match self {
+                    $(Error::$variant(e) => e.context(),)+
+                }
+            }
+
+            pub(crate) fn context_mut(&mut self) -> &mut error::ErrorContext {
+                match self {
+                    $(Error::$variant(e) => e.context_mut(),)+
+                }
+            }
+        }
+    };
+}
+
+error_context!(
+    MissingDataset,
+    SerializeArray,
+    WriteArray,
+    CreateDataset,
+    FetchDataset,
+    MissingAttribute,
+    SerializeAttribute,
+    FetchAttribute,
+    CreateAttribute,
+    WriteAttribute,
+    MissingGroup,
+    CreateGroup,
+    MissingDatatype,
+    DimensionMismatch,
+    WrongDatatype,
+    OpenFile,
+    CloseFile,
+    ReadSlice,
+    WriteSlice,
+    MmapUnsupported,
+    MapFile,
+    FileIo,
+    IncompleteWrite,
+    FileImage,
+    StartSwmr,
+    FlushDataset,
+    RefreshDataset,
+    BackgroundWriter,
+    Cancelled,
+    BlockingTask,
+    ShapeMismatch,
+    WrongObjectKind,
+    TransposeUnsupported,
+    OperationUnsupported,
+    AttributesUnsupported,
+    UnitsMismatch,
+    Aggregate,
+);
+
+impl Error {
+    /// broad category of the error, for matching without listing every variant
+    ///
+    /// ```
+    /// use hdf5_derive::{ContainerRead, ContainerWrite};
+    /// use hdf5_derive::error::ErrorKind;
+    /// use ndarray::{Array2, Array3};
+    ///
+    /// #[derive(ContainerWrite)]
+    /// struct Written {
+    ///     pressure: Array2<f64>,
+    /// }
+    ///
+    /// #[derive(ContainerRead)]
+    /// struct Read {
+    ///     pressure: Array3<f64>,
+    /// }
+    ///
+    /// let file = hdf5_derive::memory::create_in_memory().unwrap();
+    /// Written { pressure: Array2::zeros((2, 2)) }.write_hdf5(&file).unwrap();
+    ///
+    /// let error = Read::read_hdf5(&file).err().unwrap();
+    /// assert_eq!(error.kind(), ErrorKind::DimensionMismatch);
+    ///
+    /// if let hdf5_derive::Error::DimensionMismatch(mismatch) = error {
+    ///     assert_eq!((mismatch.expected(), mismatch.actual()), (3, 2));
+    /// }
+    /// ```
+    pub fn kind(&self) -> error::ErrorKind {
+        use error::ErrorKind;
+
+        match self {
+            Error::MissingDataset(_)
+            | Error::MissingAttribute(_)
+            | Error::MissingGroup(_)
+            | Error::FetchDataset(_)
+            | Error::FetchAttribute(_) => ErrorKind::NotFound,
+            Error::DimensionMismatch(_) => ErrorKind::DimensionMismatch,
+            Error::ShapeMismatch(_) => ErrorKind::ShapeMismatch,
+            Error::WrongObjectKind(_) => ErrorKind::WrongObjectKind,
+            Error::UnitsMismatch(_) => ErrorKind::UnitsMismatch,
+            Error::Aggregate(_) => ErrorKind::Multiple,
+            Error::WrongDatatype(_) => ErrorKind::DatatypeMismatch,
+            Error::SerializeArray(_)
+            | Error::SerializeAttribute(_)
+            | Error::MissingDatatype(_)
+            | Error::ReadSlice(_)
+            | Error::RefreshDataset(_) => ErrorKind::Read,
+            Error::WriteArray(_)
+            | Error::CreateDataset(_)
+            | Error::CreateGroup(_)
+            | Error::CreateAttribute(_)
+            | Error::WriteAttribute(_)
+            | Error::WriteSlice(_)
+            | Error::FlushDataset(_) => ErrorKind::Write,
+            Error::OpenFile(_)
+            | Error::CloseFile(_)
+            | Error::FileIo(_)
+            | Error::MapFile(_)
+            | Error::FileImage(_)
+            | Error::StartSwmr(_) => ErrorKind::File,
+            Error::MmapUnsupported(_)
+            | Error::TransposeUnsupported(_)
+            | Error::OperationUnsupported(_)
+            | Error::AttributesUnsupported(_) => ErrorKind::Unsupported,
+            Error::IncompleteWrite(_) => ErrorKind::IncompleteWrite,
+            Error::Cancelled(_) => ErrorKind::Cancelled,
+            Error::BackgroundWriter(_) | Error::BlockingTask(_) => ErrorKind::Task,
+        }
+    }
+
+    /// full HDF5 path of the dataset, attribute or group that failed, if the error happened while
+    /// reading or writing a field of a derived container
+    ///
+    /// ```
+    /// use hdf5_derive::{ContainerRead, ContainerWrite};
+    /// use ndarray::Array2;
+    ///
+    /// #[derive(ContainerRead, ContainerWrite)]
+    /// struct Nested {
+    ///     vorticity: Array2<f32>,
+    /// }
+    ///
+    /// #[derive(ContainerRead, ContainerWrite)]
+    /// struct Data {
+    ///     #[hdf5(rename(both = "supplemental"))]
+    ///     supplemental_information: Nested,
+    /// }
+    ///
+    /// let file = hdf5_derive::memory::create_in_memory().unwrap();
+    /// // a group without the `vorticity` dataset
+    /// file.create_group("supplemental").unwrap();
+    ///
+    /// let error = Data::read_hdf5(&file).err().unwrap();
+    ///
+    /// assert_eq!(error.path(), Some("/supplemental/vorticity"));
+    /// assert_eq!(error.field_path().as_deref(), Some("supplemental_information.vorticity"));
+    /// assert_eq!(error.file_name(), Some(file.filename().as_str()));
+    /// ```
+    pub fn path(&self) -> Option<&str> {
+        self.context().path()
+    }
+
+    /// chain of Rust field names leading to the failure, separated by `.`
+    pub fn field_path(&self) -> Option<String> {
+        self.context().field_path()
+    }
+
+    /// name of the file that was being read or written when the error happened
+    pub fn file_name(&self) -> Option<&str> {
+        self.context().file_name()
+    }
 }
 
 /// Helper trait to determine the type of element that a given [`ArrayBase`](ndarray::ArrayBase)
@@ -177,14 +687,37 @@ macro_rules! attributes{
         $(
             impl ReadGroup for $scalar_type {
                 fn read_group(group: &Group, attribute_name: &str, _transpose: bool) -> Result<Self, Error> where Self: Sized {
+                    #[cfg(feature = "tracing")]
+                    let mut transfer = instrument::Transfer::start::<Self>(progress::Operation::Read, group, attribute_name);
+
                     let attribute_handle = group.attr(attribute_name)
                         .map_err(|e| error::MissingAttribute::from_field_name(attribute_name, e))?;
 
                     let attribute: Self = attribute_handle.read_scalar()
                         .map_err(|e| error::SerializeAttribute::from_field_name(attribute_name, e))?;
 
+                    progress::record_bytes(std::mem::size_of::<Self>());
+                    #[cfg(feature = "tracing")]
+                    transfer.shape(&[]);
+
                     Ok(attribute)
                 }
+
+                fn validate_group(group: &Group, attribute_name: &str, _transpose: bool, report: &mut validate::ValidationReport) where Self: Sized {
+                    let attribute = match validate::attribute(group, attribute_name, report) {
+                        Some(attribute) => attribute,
+                        None => return,
+                    };
+
+                    let checks = [
+                        check_dimension::<ndarray::Ix0>(&attribute, attribute_name),
+                        check_datatype::<Self>(&attribute, attribute_name),
+                    ];
+
+                    if report.push_results(checks) {
+                        report.push_checked(group, attribute_name, validate::ObjectKind::Attribute, &[], false);
+                    }
+                }
             }
             impl WriteGroup for $scalar_type {
                 fn write_group(
@@ -197,6 +730,9 @@ macro_rules! attributes{
                 where
                     Self: Sized
                 {
+                    #[cfg(feature = "tracing")]
+                    let mut transfer = instrument::Transfer::start::<Self>(progress::Operation::Write, group, attribute_name);
+
                     let hdf5_attribute = if mutate_on_write {
                         group.attr(attribute_name)
                             .map_err(|e| error::FetchAttribute::from_field_name(attribute_name, e))?
@@ -209,9 +745,49 @@ macro_rules! attributes{
                     hdf5_attribute.write_scalar(self)
                         .map_err(|e| error::WriteAttribute::from_field_name(attribute_name, e))?;
 
+                    progress::record_bytes(std::mem::size_of::<Self>());
+                    #[cfg(feature = "tracing")]
+                    transfer.shape(&[]);
 
                     Ok(())
                 }
+
+                /// every block holds the same value, so the attribute written by an earlier block (or
+                /// preallocated in the file) is overwritten, and only created if it does not exist yet
+                fn write_group_selection(
+                    &self,
+                    group: &Group,
+                    attribute_name: &str,
+                    transpose: bool,
+                    _mutate_on_write: bool,
+                    _offset: usize,
+                ) -> Result<(), Error>
+                where
+                    Self: Sized
+                {
+                    let exists = metadata::has_attribute(group, attribute_name);
+                    self.write_group(group, attribute_name, transpose, exists)
+                }
+
+                /// attributes cannot be extended, the value written when the file was created is kept
+                fn append_group(&self, _group: &Group, _attribute_name: &str, _transpose: bool) -> Result<(), Error>
+                where
+                    Self: Sized
+                {
+                    Ok(())
+                }
+            }
+            impl schema::FieldLayout for $scalar_type {
+                fn layout() -> schema::Layout {
+                    schema::attribute_layout::<Self>()
+                }
+            }
+            impl LazyTwin for $scalar_type {
+                type Lazy = Self;
+
+                fn from_lazy(lazy: Self::Lazy, _transpose: bool) -> Result<Self, Error> {
+                    Ok(lazy)
+                }
             }
         )+
     }
@@ -219,6 +795,67 @@ macro_rules! attributes{
 
 attributes!(f32, f64, i16, i32, i64, i8, isize, u16, u8, u32, u64, usize);
 
+/// select `selection` along `selected_axis` of an `ndim` dimensional dataset, and the full extent
+/// of every other axis
+pub(crate) fn axis_selection(
+    ndim: usize,
+    selected_axis: usize,
+    selection: Slice,
+) -> ndarray::SliceInfo<Vec<ndarray::SliceInfoElem>, ndarray::IxDyn, ndarray::IxDyn> {
+    let elements: Vec<ndarray::SliceInfoElem> = (0..ndim)
+        .map(|axis| {
+            if axis == selected_axis {
+                selection.into()
+            } else {
+                (..).into()
+            }
+        })
+        .collect();
+
+    ndarray::SliceInfo::try_from(elements)
+        .expect("dynamic dimension slices always match the number of elements")
+}
+
+/// check that `container` has the dimension `D`, so that reading it fails with a structured error
+/// instead of an opaque HDF5 error
+pub(crate) fn check_dimension<D>(container: &hdf5::Container, name: &str) -> Result<(), Error>
+where
+    D: ndarray::Dimension,
+{
+    match D::NDIM {
+        Some(ndim) if container.ndim() != ndim => {
+            Err(error::DimensionMismatch::new(name, container.ndim(), ndim).into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// check that `container` has a datatype that can be converted to `S`
+pub(crate) fn check_datatype<S>(container: &hdf5::Container, name: &str) -> Result<(), Error>
+where
+    S: hdf5::H5Type,
+{
+    let dtype = container
+        .dtype()
+        .map_err(|e| error::MissingDatatype::from_field_name(name, e))?;
+
+    if dtype.conv_to::<S>().is_none() {
+        return Err(error::WrongDatatype::new(name, S::type_descriptor(), dtype).into());
+    }
+
+    Ok(())
+}
+
+/// check that `dataset` can be read into an array with element `S` and dimension `D`
+fn check_readable<S, D>(dataset: &hdf5::Dataset, array_name: &str) -> Result<(), Error>
+where
+    S: hdf5::H5Type,
+    D: ndarray::Dimension,
+{
+    check_dimension::<D>(dataset, array_name)?;
+    check_datatype::<S>(dataset, array_name)
+}
+
 /// Defines how a given piece of data should be parsed.
 /// You likely do not want to use this trait; instead use the methods from [`ContainerRead`]
 pub trait ReadGroup {
@@ -227,6 +864,47 @@ pub trait ReadGroup {
     fn read_group(group: &Group, array_name: &str, transpose: bool) -> Result<Self, Error>
     where
         Self: Sized;
+
+    /// Same as [`ReadGroup::read_group`], but only read the part of the data that lies in
+    /// `selection` along the leading axis. Types without a leading axis (such as scalar
+    /// attributes) ignore the selection.
+    fn read_group_selection(
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        _selection: Slice,
+    ) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Self::read_group(group, array_name, transpose)
+    }
+
+    /// Same as [`ReadGroup::read_group`], but nested containers are read with
+    /// [`ContainerRead::read_hdf5_report`], so that the errors of all their fields are returned
+    fn read_group_report(group: &Group, array_name: &str, transpose: bool) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Self::read_group(group, array_name, transpose)
+    }
+
+    /// Check that `array_name` could be read from `group` with [`ReadGroup::read_group`] without
+    /// reading any data, recording every problem in `report`. Types that do not implement this
+    /// are listed in [`validate::ValidationReport::unchecked`].
+    fn validate_group(
+        group: &Group,
+        array_name: &str,
+        _transpose: bool,
+        report: &mut validate::ValidationReport,
+    ) where
+        Self: Sized,
+    {
+        report.push_unchecked(
+            validate::object_path(group, array_name),
+            std::any::type_name::<Self>(),
+        );
+    }
 }
 
 impl<S, D> ReadGroup for ndarray::ArrayBase<ndarray::OwnedRepr<S>, D>
@@ -238,13 +916,93 @@ where
     where
         Self: Sized,
     {
+        #[cfg(feature = "tracing")]
+        let mut transfer =
+            instrument::Transfer::start::<S>(progress::Operation::Read, group, array_name);
+
         let dataset = group
             .dataset(array_name)
             .map_err(|e| MissingDataset::from_field_name(array_name, e))?;
+
+        check_readable::<S, D>(&dataset, array_name)?;
+
+        #[cfg(feature = "swmr")]
+        if swmr::mode() == Some(swmr::Mode::Refresh) {
+            swmr::refresh(&dataset, array_name)?;
+        }
+
         let output_array: Self = dataset
             .read()
             .map_err(|e| SerializeArray::from_field_name(array_name, e))?;
 
+        progress::record_bytes(output_array.len() * std::mem::size_of::<S>());
+        #[cfg(feature = "tracing")]
+        transfer.shape(output_array.shape());
+
+        // handle transposing the array
+        let output_array = if transpose {
+            output_array.reversed_axes()
+        } else {
+            output_array
+        };
+
+        Ok(output_array)
+    }
+
+    fn read_group_selection(
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        selection: Slice,
+    ) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        #[cfg(feature = "tracing")]
+        let mut transfer =
+            instrument::Transfer::start::<S>(progress::Operation::Read, group, array_name);
+
+        let dataset = group
+            .dataset(array_name)
+            .map_err(|e| MissingDataset::from_field_name(array_name, e))?;
+
+        check_readable::<S, D>(&dataset, array_name)?;
+
+        #[cfg(feature = "swmr")]
+        if swmr::mode() == Some(swmr::Mode::Refresh) {
+            swmr::refresh(&dataset, array_name)?;
+        }
+
+        // the leading axis of a transposed array is the last axis of the dataset
+        let ndim = dataset.ndim();
+        let selected_axis = if transpose { ndim.saturating_sub(1) } else { 0 };
+
+        #[cfg(feature = "mpi")]
+        if parallel::collective().is_some() {
+            let output_array: Self =
+                parallel::read_array(&dataset, array_name, selected_axis, selection)?;
+
+            progress::record_bytes(output_array.len() * std::mem::size_of::<S>());
+            #[cfg(feature = "tracing")]
+            transfer.shape(output_array.shape());
+
+            return Ok(if transpose {
+                output_array.reversed_axes()
+            } else {
+                output_array
+            });
+        }
+
+        let info = axis_selection(ndim, selected_axis, selection);
+
+        let output_array: Self = dataset
+            .read_slice(info)
+            .map_err(|e| error::ReadSlice::from_field_name(array_name, e))?;
+
+        progress::record_bytes(output_array.len() * std::mem::size_of::<S>());
+        #[cfg(feature = "tracing")]
+        transfer.shape(output_array.shape());
+
         // handle transposing the array
         let output_array = if transpose {
             output_array.reversed_axes()
@@ -254,6 +1012,35 @@ where
 
         Ok(output_array)
     }
+
+    fn validate_group(
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        report: &mut validate::ValidationReport,
+    ) where
+        Self: Sized,
+    {
+        let dataset = match validate::dataset(group, array_name, report) {
+            Some(dataset) => dataset,
+            None => return,
+        };
+
+        let checks = [
+            check_dimension::<D>(&dataset, array_name),
+            check_datatype::<S>(&dataset, array_name),
+        ];
+
+        if report.push_results(checks) {
+            report.push_checked(
+                group,
+                array_name,
+                validate::ObjectKind::Dataset,
+                &dataset.shape(),
+                transpose,
+            );
+        }
+    }
 }
 
 impl<T> ReadGroup for T
@@ -270,6 +1057,85 @@ where
 
         T::read_hdf5(&subgroup)
     }
+
+    fn read_group_selection(
+        group: &Group,
+        container_name: &str,
+        _transpose: bool,
+        selection: Slice,
+    ) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let subgroup: Group = group
+            .group(container_name)
+            .map_err(|e| error::MissingGroup::from_field_name(container_name, e))?;
+
+        T::read_hdf5_selection(&subgroup, selection)
+    }
+
+    fn read_group_report(
+        group: &Group,
+        container_name: &str,
+        _transpose: bool,
+    ) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let subgroup: Group = group
+            .group(container_name)
+            .map_err(|e| error::MissingGroup::from_field_name(container_name, e))?;
+
+        T::read_hdf5_report(&subgroup)
+    }
+
+    fn validate_group(
+        group: &Group,
+        container_name: &str,
+        _transpose: bool,
+        report: &mut validate::ValidationReport,
+    ) where
+        Self: Sized,
+    {
+        if let Some(subgroup) = validate::group(group, container_name, report) {
+            report.push_checked(
+                group,
+                container_name,
+                validate::ObjectKind::Group,
+                &[],
+                false,
+            );
+            report.append(T::validate_hdf5(&subgroup));
+        }
+    }
+}
+
+/// Maps a field type to the type used in place of it in a lazy twin struct generated with
+/// `#[hdf5(lazy_twin = "...")]`, and materializes the eager type from it.
+/// You likely do not want to use this trait; instead use the `load` method generated on the lazy twin.
+///
+/// Owned arrays map to a [`LazyArray`] of the same type and dimension, nested containers map to
+/// [`Lazy`], and scalar attributes are read eagerly.
+pub trait LazyTwin {
+    /// the type of the field in the lazy twin struct
+    type Lazy: ReadGroup;
+
+    /// read the full value from its lazy counterpart, applying `transpose` from the field's
+    /// read attributes
+    fn from_lazy(lazy: Self::Lazy, transpose: bool) -> Result<Self, Error>
+    where
+        Self: Sized;
+}
+
+impl<T> LazyTwin for T
+where
+    T: ContainerRead,
+{
+    type Lazy = Lazy<T>;
+
+    fn from_lazy(lazy: Self::Lazy, _transpose: bool) -> Result<Self, Error> {
+        lazy.into_inner()
+    }
 }
 
 /// Defines how a given piece of data should be written.
@@ -286,6 +1152,35 @@ pub trait WriteGroup {
     ) -> Result<(), Error>
     where
         Self: Sized;
+
+    /// Same as [`WriteGroup::write_group`], but write the data into an existing dataset starting
+    /// at `offset` along the leading axis. Types without a leading axis are written with
+    /// [`WriteGroup::write_group`], except scalar attributes, which are overwritten if they exist.
+    fn write_group_selection(
+        &self,
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        mutate_on_write: bool,
+        _offset: usize,
+    ) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        self.write_group(group, array_name, transpose, mutate_on_write)
+    }
+
+    /// Append the data to the end of the existing dataset `array_name` along the leading axis, for
+    /// [`ContainerWrite::append_hdf5`]. Types without a leading axis, such as scalar attributes, are
+    /// left unchanged.
+    ///
+    /// Types that do not override this method return [`error::OperationUnsupported`].
+    fn append_group(&self, _group: &Group, _array_name: &str, _transpose: bool) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        Err(error::OperationUnsupported::new(std::any::type_name::<Self>(), "append_group").into())
+    }
 }
 
 impl<S, D> WriteGroup for ndarray::ArrayBase<ndarray::OwnedRepr<S>, D>
@@ -306,6 +1201,29 @@ where
         self.view()
             .write_group(group, array_name, transpose, mutate_on_write)
     }
+
+    fn write_group_selection(
+        &self,
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        mutate_on_write: bool,
+        offset: usize,
+    ) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        self.view()
+            .write_group_selection(group, array_name, transpose, mutate_on_write, offset)
+    }
+
+    #[cfg(feature = "swmr")]
+    fn append_group(&self, group: &Group, array_name: &str, transpose: bool) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        self.view().append_group(group, array_name, transpose)
+    }
 }
 
 impl<'a, S, D> WriteGroup for ndarray::ArrayBase<ndarray::ViewRepr<&'a S>, D>
@@ -331,16 +1249,38 @@ where
             return tmp.write_group(group, array_name, false, mutate_on_write);
         }
 
+        #[cfg(feature = "tracing")]
+        let mut transfer =
+            instrument::Transfer::start::<S>(progress::Operation::Write, group, array_name);
+
         let fetch_dataset = if mutate_on_write {
             // fetch an existing dataset that we can mutate
-            group
+            let dataset = group
                 .dataset(array_name)
-                .map_err(|e| error::FetchDataset::from_field_name(array_name, e))?
+                .map_err(|e| error::FetchDataset::from_field_name(array_name, e))?;
+
+            if dataset.shape() != self.shape() {
+                return Err(
+                    error::ShapeMismatch::new(array_name, self.shape(), &dataset.shape()).into(),
+                );
+            }
+
+            dataset
         } else {
+            let extents = hdf5::Extents::from(self.shape());
+
+            // datasets of a SWMR file must be able to grow when containers are appended
+            #[cfg(feature = "swmr")]
+            let extents = if swmr::mode() == Some(swmr::Mode::Create) {
+                extents.resizable()
+            } else {
+                extents
+            };
+
             // create a new dataset
             group
                 .new_dataset::<<Self as ArrayType>::Ty>()
-                .shape(self.shape())
+                .shape(extents)
                 .create(array_name)
                 .map_err(|e| error::CreateDataset::from_field_name(array_name, e))?
         };
@@ -349,6 +1289,101 @@ where
             .write(self.view())
             .map_err(|e| error::WriteArray::from_field_name(array_name, e))?;
 
+        progress::record_bytes(self.len() * std::mem::size_of::<S>());
+        #[cfg(feature = "tracing")]
+        transfer.shape(self.shape());
+
+        Ok(())
+    }
+
+    fn write_group_selection(
+        &self,
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        _mutate_on_write: bool,
+        offset: usize,
+    ) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        #[cfg(feature = "tracing")]
+        let mut transfer =
+            instrument::Transfer::start::<S>(progress::Operation::Write, group, array_name);
+
+        // the leading axis of the array is the last axis of the dataset when transposing
+        let (view, selected_axis) = if transpose {
+            (self.t(), self.ndim().saturating_sub(1))
+        } else {
+            (self.view(), 0)
+        };
+
+        #[cfg(feature = "mpi")]
+        if let Some(parallel::Collective::Write { global_len }) = parallel::collective() {
+            parallel::write_array(
+                group,
+                array_name,
+                view.into_dyn(),
+                selected_axis,
+                offset,
+                global_len,
+            )?;
+
+            progress::record_bytes(self.len() * std::mem::size_of::<S>());
+            #[cfg(feature = "tracing")]
+            transfer.shape(self.shape());
+
+            return Ok(());
+        }
+
+        // the dataset must already exist to write a region of it
+        let dataset = group
+            .dataset(array_name)
+            .map_err(|e| error::FetchDataset::from_field_name(array_name, e))?;
+
+        // hdf5 can only write slices from standard layout arrays
+        let array = view.as_standard_layout();
+
+        let len = array.shape().get(selected_axis).copied().unwrap_or(0);
+        let info = axis_selection(
+            array.ndim(),
+            selected_axis,
+            Slice::from(offset..offset + len),
+        );
+
+        dataset
+            .write_slice(array.view(), info)
+            .map_err(|e| error::WriteSlice::from_field_name(array_name, e))?;
+
+        progress::record_bytes(self.len() * std::mem::size_of::<S>());
+        #[cfg(feature = "tracing")]
+        transfer.shape(self.shape());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "swmr")]
+    fn append_group(&self, group: &Group, array_name: &str, transpose: bool) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        #[cfg(feature = "tracing")]
+        let mut transfer =
+            instrument::Transfer::start::<S>(progress::Operation::Write, group, array_name);
+
+        // the leading axis of the array is the last axis of the dataset when transposing
+        let (view, selected_axis) = if transpose {
+            (self.t(), self.ndim().saturating_sub(1))
+        } else {
+            (self.view(), 0)
+        };
+
+        swmr::append_array(group, array_name, view.into_dyn(), selected_axis)?;
+
+        progress::record_bytes(self.len() * std::mem::size_of::<S>());
+        #[cfg(feature = "tracing")]
+        transfer.shape(self.shape());
+
         Ok(())
     }
 }
@@ -381,4 +1416,51 @@ where
 
         Ok(())
     }
+
+    fn write_group_selection(
+        &self,
+        group: &Group,
+        container_name: &str,
+        _transpose: bool,
+        _mutate_on_write: bool,
+        offset: usize,
+    ) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        // collective writes create missing groups and datasets
+        #[cfg(feature = "mpi")]
+        if parallel::collective().is_some() && !group.link_exists(container_name) {
+            let subgroup = group
+                .create_group(container_name)
+                .map_err(|e| error::CreateGroup::from_field_name(container_name, e))?;
+
+            return self.write_hdf5_selection(&subgroup, offset);
+        }
+
+        // the datasets in the group already exist, so the group does too
+        let subgroup = group
+            .group(container_name)
+            .map_err(|e| error::MissingGroup::from_field_name(container_name, e))?;
+
+        self.write_hdf5_selection(&subgroup, offset)?;
+
+        Ok(())
+    }
+
+    fn append_group(
+        &self,
+        group: &Group,
+        container_name: &str,
+        _transpose: bool,
+    ) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        let subgroup = group
+            .group(container_name)
+            .map_err(|e| error::MissingGroup::from_field_name(container_name, e))?;
+
+        self.append_hdf5(&subgroup)
+    }
 }
@@ -0,0 +1,178 @@
+//! In-memory HDF5 files and file images
+//!
+//! Files created with [`create_in_memory`] use the HDF5 core driver, and never touch the disk.
+//! [`file_image`] copies the contents of any open file into a `Vec<u8>`, which can be reopened
+//! read-only with [`open_image`]. This is useful for tests that would otherwise collide on file
+//! names when run in parallel, and for passing results between stages of a pipeline.
+//!
+//! [`crate::ContainerWrite::write_hdf5_to_image`] and [`crate::ContainerRead::read_hdf5_from_image`]
+//! cover the common case:
+//!
+//! ```
+//! use hdf5_derive::{ContainerRead, ContainerWrite};
+//! use ndarray::Array2;
+//!
+//! #[derive(ContainerRead, ContainerWrite)]
+//! struct Data {
+//!     pressure: Array2<f64>,
+//! }
+//!
+//! let data = Data { pressure: Array2::ones((4, 4)) };
+//! let image: Vec<u8> = data.write_hdf5_to_image().unwrap();
+//!
+//! let read = Data::read_hdf5_from_image(&image).unwrap();
+//! assert_eq!(read.pressure, data.pressure);
+//! ```
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use hdf5::File;
+
+use hdf5_sys::h5f::{H5Fget_file_image, H5Fopen, H5F_ACC_RDONLY};
+use hdf5_sys::h5p::H5Pset_file_image;
+
+use crate::error;
+use crate::Error;
+
+/// create a new, empty HDF5 file that only exists in memory
+///
+/// The file is discarded when it is closed. Use [`file_image`] to keep its contents.
+pub fn create_in_memory() -> Result<File, Error> {
+    let name = unique_name();
+
+    File::with_options()
+        .with_fapl(|fapl| fapl.core_filebacked(false))
+        .create(&name)
+        .map_err(|e| error::OpenFile::from_field_name(&name, e).into())
+}
+
+/// copy the contents of `file` (in memory, or on disk) into a file image
+pub fn file_image(file: &File) -> Result<Vec<u8>, Error> {
+    let name = file.filename();
+
+    file.flush()
+        .map_err(|e| error::FileImage::from_field_name(&name, e))?;
+
+    let image = hdf5::sync::sync(|| -> hdf5::Result<Vec<u8>> {
+        // the first call only returns the size of the image
+        let len = hdf5::h5check(unsafe { H5Fget_file_image(file.id(), std::ptr::null_mut(), 0) })?;
+
+        let mut image = vec![0u8; len as usize];
+        hdf5::h5check(unsafe {
+            H5Fget_file_image(file.id(), image.as_mut_ptr().cast(), image.len())
+        })?;
+
+        Ok(image)
+    })
+    .map_err(|e| error::FileImage::from_field_name(&name, e))?;
+
+    Ok(image)
+}
+
+/// open a file image (from [`file_image`], or the bytes of an HDF5 file) as a read-only, in-memory file
+///
+/// `image` is copied, so it can be dropped while the file is open.
+pub fn open_image(image: &[u8]) -> Result<File, Error> {
+    let name = unique_name();
+
+    let fapl = hdf5::plist::FileAccess::build()
+        .core_filebacked(false)
+        .finish()
+        .map_err(|e| error::FileImage::from_field_name(&name, e))?;
+
+    let file = hdf5::sync::sync(|| -> hdf5::Result<File> {
+        // HDF5 copies the buffer, and never writes to it
+        hdf5::h5check(unsafe {
+            H5Pset_file_image(fapl.id(), image.as_ptr() as *mut _, image.len())
+        })?;
+
+        let c_name = std::ffi::CString::new(name.as_str())
+            .expect("generated names do not contain null bytes");
+        let id = hdf5::h5check(unsafe { H5Fopen(c_name.as_ptr(), H5F_ACC_RDONLY, fapl.id()) })?;
+
+        unsafe { hdf5::from_id::<File>(id) }
+    })
+    .map_err(|e| error::FileImage::from_field_name(&name, e))?;
+
+    Ok(file)
+}
+
+/// HDF5 tracks open files by name, so every in-memory file needs a name that is unique within the process
+fn unique_name() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("hdf5_derive_in_memory_{}_{}.h5", std::process::id(), count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContainerRead, ContainerWrite};
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::Array2;
+
+    #[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+    struct Nested {
+        arr: Array2<f32>,
+    }
+
+    #[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+    struct Data {
+        arr: Array2<u32>,
+        value: u64,
+        nested: Nested,
+    }
+
+    #[test]
+    fn image_roundtrip() {
+        let data = Data {
+            arr: Array2::from_shape_fn((3, 4), |(i, j)| (i * 4 + j) as u32),
+            value: 7,
+            nested: Nested {
+                arr: Array2::ones((2, 2)),
+            },
+        };
+
+        let file = create_in_memory().unwrap();
+        data.write_hdf5(&file).unwrap();
+
+        let image = file_image(&file).unwrap();
+        file.close().unwrap();
+
+        let file = open_image(&image).unwrap();
+        drop(image);
+
+        assert!(file.is_read_only());
+        assert_eq!(Data::read_hdf5(&file).unwrap(), data);
+    }
+
+    #[test]
+    fn in_memory_files_are_independent() {
+        let first = create_in_memory().unwrap();
+        let second = create_in_memory().unwrap();
+
+        let data = Data {
+            arr: Array2::zeros((3, 4)),
+            value: 7,
+            nested: Nested {
+                arr: Array2::ones((2, 2)),
+            },
+        };
+        data.write_hdf5(&first).unwrap();
+
+        assert!(Data::read_hdf5(&first).is_ok());
+        assert!(Data::read_hdf5(&second).is_err());
+    }
+
+    #[test]
+    fn open_invalid_image() {
+        assert!(matches!(
+            open_image(b"not an hdf5 file"),
+            Err(Error::FileImage(_))
+        ));
+    }
+}
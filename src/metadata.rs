@@ -0,0 +1,427 @@
+//! String attributes describing datasets and groups
+//!
+//! ## Descriptions
+//!
+//! Fields of a container deriving [`crate::ContainerWrite`] with `#[hdf5(write_docs)]` have their `///` doc
+//! comments written to a [`DESCRIPTION`] attribute on the dataset or group backing them, so that the meaning
+//! of each field is visible in HDFView or h5py. `write_docs` on the container also describes the container's
+//! own group with the doc comments of the struct, and enables the attribute for every field. It can be turned
+//! off for a single field with `#[hdf5(write_docs = false)]`.
+//!
+//! Scalar fields are stored as attributes, which cannot have attributes of their own, so their doc comments are
+//! written to a `<name>_description` attribute next to them instead.
+//!
+//! ```
+//! use hdf5_derive::ContainerWrite;
+//! use hdf5_derive::metadata::{string_attribute, DESCRIPTION};
+//! use ndarray::Array2;
+//!
+//! #[derive(ContainerWrite)]
+//! #[hdf5(write_docs)]
+//! /// the flow field at a single time step
+//! struct Snapshot {
+//!     /// streamwise velocity, in m/s
+//!     velocity: Array2<f64>,
+//! }
+//!
+//! let file = hdf5_derive::memory::create_in_memory().unwrap();
+//! Snapshot { velocity: Array2::zeros((4, 4)) }.write_hdf5(&file).unwrap();
+//!
+//! let velocity = file.dataset("velocity").unwrap();
+//! assert_eq!(string_attribute(&velocity, DESCRIPTION).unwrap(), "streamwise velocity, in m/s");
+//! assert_eq!(string_attribute(&file, DESCRIPTION).unwrap(), "the flow field at a single time step");
+//! ```
+//!
+//! ## Units
+//!
+//! Fields with `#[hdf5(units = "m/s")]` have their units written to a [`UNITS`] attribute. Scalar fields
+//! cannot carry attributes, so their units are written to a `<name>_units` attribute next to them, and no
+//! other field of a container may be named `<name>_units` if `<name>` has units (this is a compile error). With
+//! `#[hdf5(check_units)]` on the field or on the container, reading fails with [`crate::error::UnitsMismatch`]
+//! if the file records different units, or none at all. The units are compared as plain strings.
+//!
+//! ```
+//! use hdf5_derive::{ContainerRead, ContainerWrite};
+//! use hdf5_derive::metadata::{string_attribute, UNITS};
+//! use ndarray::Array2;
+//!
+//! #[derive(ContainerWrite)]
+//! struct Written {
+//!     #[hdf5(units = "km/h")]
+//!     velocity: Array2<f64>,
+//! }
+//!
+//! #[derive(ContainerRead)]
+//! struct Read {
+//!     #[hdf5(units = "m/s", check_units)]
+//!     velocity: Array2<f64>,
+//! }
+//!
+//! let file = hdf5_derive::memory::create_in_memory().unwrap();
+//! Written { velocity: Array2::zeros((4, 4)) }.write_hdf5(&file).unwrap();
+//!
+//! let velocity = file.dataset("velocity").unwrap();
+//! assert_eq!(string_attribute(&velocity, UNITS).unwrap(), "km/h");
+//!
+//! let error = Read::read_hdf5(&file).err().unwrap();
+//! assert_eq!(error.kind(), hdf5_derive::error::ErrorKind::UnitsMismatch);
+//! ```
+
+use hdf5::types::VarLenUnicode;
+use hdf5::Group;
+use hdf5::H5Type;
+use hdf5::Location;
+
+use crate::error;
+use crate::Error;
+
+/// name of the attribute holding the doc comments of a field
+pub const DESCRIPTION: &str = "description";
+
+/// name of the attribute holding the units of a field
+pub const UNITS: &str = "units";
+
+/// write `value` to the string attribute `name` of `location`, replacing the value of an existing attribute
+pub fn write_string_attribute(location: &Location, name: &str, value: &str) -> Result<(), Error> {
+    let value: VarLenUnicode = value.parse().map_err(|e: hdf5::types::StringError| {
+        error::WriteAttribute::from_field_name(name, e.to_string().into())
+    })?;
+
+    write_scalar_attribute(location, name, &value)
+}
+
+/// write `value` to the scalar attribute `name` of `location`, replacing the value of an existing attribute
+pub fn write_scalar_attribute<T: H5Type>(
+    location: &Location,
+    name: &str,
+    value: &T,
+) -> Result<(), Error> {
+    let attribute = if has_attribute(location, name) {
+        location
+            .attr(name)
+            .map_err(|e| error::FetchAttribute::from_field_name(name, e))?
+    } else {
+        location
+            .new_attr::<T>()
+            .create(name)
+            .map_err(|e| error::CreateAttribute::from_field_name(name, e))?
+    };
+
+    attribute
+        .write_scalar(value)
+        .map_err(|e| error::WriteAttribute::from_field_name(name, e))?;
+
+    Ok(())
+}
+
+/// read the string attribute `name` of `location`
+pub fn string_attribute(location: &Location, name: &str) -> Result<String, Error> {
+    let value: VarLenUnicode = scalar_attribute(location, name)?;
+
+    Ok(value.as_str().to_string())
+}
+
+/// read the scalar attribute `name` of `location`
+pub fn scalar_attribute<T: H5Type>(location: &Location, name: &str) -> Result<T, Error> {
+    let attribute = location
+        .attr(name)
+        .map_err(|e| error::MissingAttribute::from_field_name(name, e))?;
+
+    attribute
+        .read_scalar()
+        .map_err(|e| error::SerializeAttribute::from_field_name(name, e).into())
+}
+
+/// whether `location` has an attribute `name`
+pub(crate) fn has_attribute(location: &Location, name: &str) -> bool {
+    location
+        .attr_names()
+        .is_ok_and(|names| names.iter().any(|n| n == name))
+}
+
+/// Types written as a dataset or group, which can hold attributes of their own. Fields with `attrs(..)`, and
+/// the data of a [`crate::WithAttrs`], must implement it, so that attaching attributes to a scalar (which is
+/// itself stored as an attribute) fails to compile instead of leaving a partially written file
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is stored as an attribute, so attributes cannot be attached to it",
+    label = "scalar fields cannot hold attributes"
+)]
+pub trait StoredAsObject {}
+
+impl<S, D> StoredAsObject for ndarray::ArrayBase<ndarray::OwnedRepr<S>, D> {}
+
+impl<S, D> StoredAsObject for ndarray::ArrayBase<ndarray::ViewRepr<&S>, D> {}
+
+impl<T: crate::ContainerWrite> StoredAsObject for T {}
+
+/// the dataset or group `name` of `group`. `None` for scalar fields, which are stored as attributes
+pub(crate) fn object(group: &Group, name: &str) -> Option<Location> {
+    if !group.link_exists(name) {
+        return None;
+    }
+
+    match group.group(name) {
+        Ok(subgroup) => Some((*subgroup).clone()),
+        Err(_) => group.dataset(name).ok().map(|dataset| (**dataset).clone()),
+    }
+}
+
+/// write `description` to the [`DESCRIPTION`] attribute of the dataset or group `name` of `group`, or to the
+/// `<name>_description` attribute next to a scalar field. Called by derived code
+#[doc(hidden)]
+pub fn describe(group: &Group, name: &str, description: &str) -> Result<(), Error> {
+    match sibling_attribute(group, name, DESCRIPTION) {
+        Some((location, attribute)) => write_string_attribute(&location, &attribute, description),
+        None => Ok(()),
+    }
+}
+
+/// call `f` with the dataset or group `name` of `group`, which was written from a `T`, to write attributes to
+/// it. Fails with [`error::AttributesUnsupported`] if there is no such dataset or group. Called by derived code
+#[doc(hidden)]
+pub fn attach<T, F>(group: &Group, name: &str, f: F) -> Result<(), Error>
+where
+    T: StoredAsObject + ?Sized,
+    F: FnOnce(&Location) -> Result<(), Error>,
+{
+    match object(group, name) {
+        Some(location) => f(&location),
+        None => Err(error::AttributesUnsupported::new(name).into()),
+    }
+}
+
+/// the location and name of the attribute `attribute` of the field `name` of `group`, if the field exists
+fn sibling_attribute(group: &Group, name: &str, attribute: &str) -> Option<(Location, String)> {
+    match object(group, name) {
+        Some(location) => Some((location, attribute.to_string())),
+        // scalar fields are attributes of the group, so their metadata is written next to them
+        None if has_attribute(group, name) => {
+            Some(((**group).clone(), format!("{name}_{attribute}")))
+        }
+        None => None,
+    }
+}
+
+/// the location and name of the attribute holding the units of the field `name` of `group`, if the field exists
+fn units_attribute(group: &Group, name: &str) -> Option<(Location, String)> {
+    sibling_attribute(group, name, UNITS)
+}
+
+/// write `units` to the [`UNITS`] attribute of the field `name` of `group`. Called by derived code
+#[doc(hidden)]
+pub fn write_units(group: &Group, name: &str, units: &str) -> Result<(), Error> {
+    match units_attribute(group, name) {
+        Some((location, attribute)) => write_string_attribute(&location, &attribute, units),
+        None => Ok(()),
+    }
+}
+
+/// the doc comments recorded for the field `name` of `group` by `write_docs`. `None` if the field or its
+/// description are not in the file
+pub fn description(group: &Group, name: &str) -> Result<Option<String>, Error> {
+    match sibling_attribute(group, name, DESCRIPTION) {
+        Some((location, attribute)) if has_attribute(&location, &attribute) => {
+            string_attribute(&location, &attribute).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// the units recorded for the field `name` of `group`. `None` if the field or its units are not in the file
+pub fn units(group: &Group, name: &str) -> Result<Option<String>, Error> {
+    match units_attribute(group, name) {
+        Some((location, attribute)) if has_attribute(&location, &attribute) => {
+            string_attribute(&location, &attribute).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// check that the field `name` of `group` is stored in `expected` units. Missing fields are left for the
+/// read itself to report. Called by derived code
+#[doc(hidden)]
+pub fn check_units(group: &Group, name: &str, expected: &str) -> Result<(), Error> {
+    if units_attribute(group, name).is_none() {
+        return Ok(());
+    }
+
+    match units(group, name)? {
+        Some(actual) if actual == expected => Ok(()),
+        actual => Err(error::UnitsMismatch::new(name, expected, actual).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContainerRead, ContainerWrite};
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::Array2;
+
+    #[derive(ContainerRead, ContainerWrite)]
+    #[hdf5(write_docs)]
+    /// nested docs
+    struct Nested {
+        /// pressure docs
+        pressure: Array2<f32>,
+    }
+
+    #[derive(ContainerRead, ContainerWrite)]
+    struct Data {
+        #[hdf5(write_docs)]
+        /// velocity docs
+        velocity: Array2<f64>,
+        /// not written
+        energy: Array2<f64>,
+        #[hdf5(write_docs)]
+        /// step docs
+        step: u64,
+        nested: Nested,
+        #[hdf5(write_docs)]
+        /// field docs
+        described: Nested,
+    }
+
+    #[test]
+    fn writes_descriptions() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        let data = Data {
+            velocity: Array2::zeros((2, 2)),
+            energy: Array2::zeros((2, 2)),
+            step: 1,
+            nested: Nested {
+                pressure: Array2::zeros((2, 2)),
+            },
+            described: Nested {
+                pressure: Array2::zeros((2, 2)),
+            },
+        };
+        data.write_hdf5(&file).unwrap();
+
+        let velocity = file.dataset("velocity").unwrap();
+        assert_eq!(
+            string_attribute(&velocity, DESCRIPTION).unwrap(),
+            "velocity docs"
+        );
+
+        let energy = file.dataset("energy").unwrap();
+        assert!(string_attribute(&energy, DESCRIPTION).is_err());
+
+        // no description on the root group, since `Data` is not `write_docs`
+        assert!(string_attribute(&file, DESCRIPTION).is_err());
+
+        let nested = file.group("nested").unwrap();
+        assert_eq!(
+            string_attribute(&nested, DESCRIPTION).unwrap(),
+            "nested docs"
+        );
+
+        let pressure = nested.dataset("pressure").unwrap();
+        assert_eq!(
+            string_attribute(&pressure, DESCRIPTION).unwrap(),
+            "pressure docs"
+        );
+
+        // the docs of the field win over the docs of the struct
+        let described = file.group("described").unwrap();
+        assert_eq!(
+            string_attribute(&described, DESCRIPTION).unwrap(),
+            "field docs"
+        );
+
+        // the scalar `step` is described next to it
+        assert_eq!(
+            string_attribute(&file, "step_description").unwrap(),
+            "step docs"
+        );
+        assert_eq!(
+            description(&file, "step").unwrap().as_deref(),
+            Some("step docs")
+        );
+        assert_eq!(description(&file, "energy").unwrap(), None);
+
+        // the data is unchanged
+        assert_eq!(Data::read_hdf5(&file).unwrap().step, 1);
+    }
+
+    #[derive(ContainerRead, ContainerWrite)]
+    struct WithUnits {
+        #[hdf5(units = "m s-1")]
+        velocity: Array2<f64>,
+        #[hdf5(units = "s")]
+        time: f64,
+    }
+
+    #[derive(ContainerRead, ContainerWrite)]
+    #[hdf5(check_units)]
+    struct Checked {
+        #[hdf5(units = "m s-1")]
+        velocity: Array2<f64>,
+        #[hdf5(units = "ms")]
+        time: f64,
+    }
+
+    #[test]
+    fn writes_units() {
+        let file = crate::memory::create_in_memory().unwrap();
+        WithUnits {
+            velocity: Array2::zeros((2, 2)),
+            time: 0.5,
+        }
+        .write_hdf5(&file)
+        .unwrap();
+
+        let velocity = file.dataset("velocity").unwrap();
+        assert_eq!(string_attribute(&velocity, UNITS).unwrap(), "m s-1");
+        assert_eq!(string_attribute(&file, "time_units").unwrap(), "s");
+
+        assert_eq!(units(&file, "velocity").unwrap().as_deref(), Some("m s-1"));
+        assert_eq!(units(&file, "time").unwrap().as_deref(), Some("s"));
+        assert_eq!(units(&file, "missing").unwrap(), None);
+
+        // the units of `time` differ
+        let error = Checked::read_hdf5(&file).err().unwrap();
+        let crate::Error::UnitsMismatch(mismatch) = &error else {
+            panic!("unexpected error {error}");
+        };
+        assert_eq!(mismatch.name(), "time");
+        assert_eq!(mismatch.expected(), "ms");
+        assert_eq!(mismatch.actual(), Some("s"));
+        assert_eq!(error.field_path().as_deref(), Some("time"));
+
+        let report = Checked::validate_hdf5(&file);
+        assert_eq!(report.problems().len(), 1);
+    }
+
+    #[test]
+    fn missing_units() {
+        let file = crate::memory::create_in_memory().unwrap();
+        file.new_dataset::<f64>()
+            .shape((2, 2))
+            .create("velocity")
+            .unwrap();
+
+        let error = check_units(&file, "velocity", "m s-1").err().unwrap();
+        assert_eq!(error.kind(), crate::error::ErrorKind::UnitsMismatch);
+
+        // missing fields are reported by the read instead
+        assert!(check_units(&file, "pressure", "Pa").is_ok());
+    }
+
+    #[test]
+    fn replaces_existing_value() {
+        let file = crate::memory::create_in_memory().unwrap();
+        let dataset = file.new_dataset::<u8>().shape(2).create("values").unwrap();
+
+        write_string_attribute(&dataset, DESCRIPTION, "first").unwrap();
+        write_string_attribute(&dataset, DESCRIPTION, "a longer second value").unwrap();
+
+        assert_eq!(
+            string_attribute(&dataset, DESCRIPTION).unwrap(),
+            "a longer second value"
+        );
+    }
+}
@@ -0,0 +1,410 @@
+//! Collective reads and writes of containers with the MPI-IO driver
+//!
+//! Requires the `mpi` feature, and an HDF5 library built with parallel support.
+//!
+//! Each rank holds a block of every array in a container, decomposed along the leading axis.
+//! Files are opened on every rank with [`open_parallel`], and [`ContainerWriteParallel::write_hdf5_parallel`]
+//! creates the global datasets and writes each rank's block into them with collective hyperslab
+//! writes. [`ContainerReadParallel::read_hdf5_parallel`] is the reverse.
+//!
+//! All functions in this module are collective: every rank in the communicator must call them
+//! with the same container type. Scalar attributes, and fields marked with `#[hdf5(select = false)]`
+//! are written by every rank, so they must hold the same value on every rank.
+//!
+//! ```no_run
+//! use hdf5_derive::{ContainerRead, ContainerWrite};
+//! use hdf5_derive::parallel::{self, ContainerReadParallel, ContainerWriteParallel, OpenMode};
+//! use hdf5_derive::Slice;
+//! use ndarray::Array3;
+//!
+//! #[derive(ContainerRead, ContainerWrite)]
+//! struct Block {
+//!     velocity: Array3<f64>,
+//!     timestep: u64,
+//! }
+//!
+//! # let (comm, rank, rows_per_rank, num_ranks) = (unsafe { mpi_sys::RSMPI_COMM_WORLD }, 0, 10, 4);
+//! let file = parallel::open_parallel("global.h5", OpenMode::Create, comm).unwrap();
+//!
+//! let block = Block { velocity: Array3::zeros((rows_per_rank, 64, 64)), timestep: 100 };
+//! block
+//!     .write_hdf5_parallel(&file, rank * rows_per_rank, num_ranks * rows_per_rank)
+//!     .unwrap();
+//!
+//! let offset = rank * rows_per_rank;
+//! let block = Block::read_hdf5_parallel(&file, Slice::from(offset..offset + rows_per_rank)).unwrap();
+//! ```
+
+use std::cell::Cell;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+use hdf5::Dataset;
+use hdf5::File;
+use hdf5::Group;
+use hdf5::H5Type;
+
+use hdf5_sys::h5::{herr_t, hsize_t, H5open};
+use hdf5_sys::h5d::{H5Dget_space, H5Dread, H5Dwrite};
+use hdf5_sys::h5i::hid_t;
+use hdf5_sys::h5p::{
+    H5FD_mpio_xfer_t, H5Pclose, H5Pcreate, H5Pset_dxpl_mpio, H5P_CLS_DATASET_XFER,
+};
+use hdf5_sys::h5s::{
+    H5S_seloper_t, H5Sclose, H5Screate_simple, H5Sselect_hyperslab, H5Sselect_none,
+};
+
+use ndarray::Array;
+use ndarray::ArrayD;
+use ndarray::ArrayViewD;
+use ndarray::Dimension;
+use ndarray::IxDyn;
+
+pub use hdf5::file::OpenMode;
+pub use mpi_sys::MPI_Comm;
+
+use crate::error;
+use crate::ContainerRead;
+use crate::ContainerWrite;
+use crate::Error;
+use crate::Slice;
+
+/// open (or create) an HDF5 file with the MPI-IO driver on every rank of `comm`
+///
+/// This is a collective operation. `comm` is usually `MPI_COMM_WORLD`, which can be fetched
+/// with `world.as_raw()` from the [`mpi`](https://docs.rs/mpi) crate.
+pub fn open_parallel<P: AsRef<Path>>(
+    path: P,
+    mode: OpenMode,
+    comm: MPI_Comm,
+) -> Result<File, Error> {
+    let path = path.as_ref();
+
+    File::with_options()
+        .with_fapl(|fapl| fapl.mpio(comm, None))
+        .open_as(path, mode)
+        .map_err(|e| error::OpenFile::from_path(path, e).into())
+}
+
+/// Collectively write a container with the MPI-IO driver. Implemented for every type that
+/// implements [`ContainerWrite`].
+pub trait ContainerWriteParallel {
+    /// write this rank's block of every array into the global datasets of a file opened with
+    /// [`open_parallel`], starting at `offset` along the leading axis
+    ///
+    /// Datasets that do not exist are created with `global_len` elements along the leading
+    /// axis, and the remaining dimensions of this rank's block. Nested groups are created
+    /// if they do not exist. Apart from creating missing datasets, this behaves like
+    /// [`ContainerWrite::write_hdf5_selection`].
+    fn write_hdf5_parallel(
+        &self,
+        container: &Group,
+        offset: usize,
+        global_len: usize,
+    ) -> Result<(), Error>;
+}
+
+impl<T> ContainerWriteParallel for T
+where
+    T: ContainerWrite,
+{
+    fn write_hdf5_parallel(
+        &self,
+        container: &Group,
+        offset: usize,
+        global_len: usize,
+    ) -> Result<(), Error> {
+        let _scope = CollectiveScope::enter(Collective::Write { global_len });
+        self.write_hdf5_selection(container, offset)
+    }
+}
+
+/// Collectively read a container with the MPI-IO driver. Implemented for every type that
+/// implements [`ContainerRead`].
+pub trait ContainerReadParallel {
+    /// read this rank's block of every array from a file opened with [`open_parallel`]
+    ///
+    /// Behaves like [`ContainerRead::read_hdf5_selection`], except that arrays are read with
+    /// collective hyperslab reads. `selection` must have a step of 1.
+    fn read_hdf5_parallel(container: &Group, selection: Slice) -> Result<Self, Error>
+    where
+        Self: Sized;
+}
+
+impl<T> ContainerReadParallel for T
+where
+    T: ContainerRead,
+{
+    fn read_hdf5_parallel(container: &Group, selection: Slice) -> Result<Self, Error> {
+        let _scope = CollectiveScope::enter(Collective::Read);
+        T::read_hdf5_selection(container, selection)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// the kind of collective operation in progress on this thread
+pub(crate) enum Collective {
+    Read,
+    Write { global_len: usize },
+}
+
+thread_local! {
+    static COLLECTIVE: Cell<Option<Collective>> = const { Cell::new(None) };
+}
+
+/// the collective operation in progress on this thread, if any. The selection
+/// routines of [`crate::ReadGroup`] and [`crate::WriteGroup`] switch to collective
+/// transfers when this is set
+pub(crate) fn collective() -> Option<Collective> {
+    COLLECTIVE.with(|c| c.get())
+}
+
+/// marks a collective operation as in progress until dropped
+struct CollectiveScope {
+    previous: Option<Collective>,
+}
+
+impl CollectiveScope {
+    fn enter(collective: Collective) -> Self {
+        let previous = COLLECTIVE.with(|c| c.replace(Some(collective)));
+        Self { previous }
+    }
+}
+
+impl Drop for CollectiveScope {
+    fn drop(&mut self) {
+        COLLECTIVE.with(|c| c.set(self.previous));
+    }
+}
+
+/// collectively write `array` into the dataset `array_name`, with `offset` along `selected_axis`.
+/// The dataset is created with `global_len` along `selected_axis` if it does not exist
+pub(crate) fn write_array<T: H5Type + Clone>(
+    group: &Group,
+    array_name: &str,
+    array: ArrayViewD<'_, T>,
+    selected_axis: usize,
+    offset: usize,
+    global_len: usize,
+) -> Result<(), Error> {
+    let dataset = if group.link_exists(array_name) {
+        group
+            .dataset(array_name)
+            .map_err(|e| error::FetchDataset::from_field_name(array_name, e))?
+    } else {
+        let mut global_shape = array.shape().to_vec();
+        if let Some(len) = global_shape.get_mut(selected_axis) {
+            *len = global_len;
+        }
+
+        group
+            .new_dataset::<T>()
+            .shape(global_shape)
+            .create(array_name)
+            .map_err(|e| error::CreateDataset::from_field_name(array_name, e))?
+    };
+
+    let mut start = vec![0; array.ndim()];
+    if let Some(start) = start.get_mut(selected_axis) {
+        *start = offset;
+    }
+
+    // hdf5 reads the buffer in row major order
+    let array = array.as_standard_layout();
+
+    write_hyperslab(&dataset, array.view(), &start)
+        .map_err(|e| error::WriteSlice::from_field_name(array_name, e))?;
+
+    Ok(())
+}
+
+/// collectively read the part of `dataset` within `selection` along `selected_axis`
+pub(crate) fn read_array<T: H5Type, D: Dimension>(
+    dataset: &Dataset,
+    array_name: &str,
+    selected_axis: usize,
+    selection: Slice,
+) -> Result<Array<T, D>, Error> {
+    let shape = dataset.shape();
+
+    let mut start = vec![0; shape.len()];
+    let mut count = shape.clone();
+
+    if let Some(&len) = shape.get(selected_axis) {
+        let (first, last) = contiguous_range(selection, len).ok_or_else(|| {
+            let msg = "collective reads require a selection with a step of 1";
+            error::ReadSlice::from_field_name(array_name, msg.into())
+        })?;
+
+        start[selected_axis] = first;
+        count[selected_axis] = last - first;
+    }
+
+    let array = read_hyperslab::<T>(dataset, &start, &count)
+        .map_err(|e| error::ReadSlice::from_field_name(array_name, e))?;
+
+    array
+        .into_dimensionality()
+        .map_err(|e| error::ReadSlice::from_field_name(array_name, e.into()).into())
+}
+
+/// resolve a `Slice` with a step of 1 to a start and end index for an axis of length `len`
+fn contiguous_range(selection: Slice, len: usize) -> Option<(usize, usize)> {
+    if selection.step != 1 {
+        return None;
+    }
+
+    let resolve = |index: isize| -> usize {
+        if index < 0 {
+            len.saturating_sub(index.unsigned_abs())
+        } else {
+            (index as usize).min(len)
+        }
+    };
+
+    let first = resolve(selection.start);
+    let last = selection.end.map(resolve).unwrap_or(len).max(first);
+
+    Some((first, last))
+}
+
+fn write_hyperslab<T: H5Type>(
+    dataset: &Dataset,
+    array: ArrayViewD<'_, T>,
+    start: &[usize],
+) -> hdf5::Result<()> {
+    let dtype = hdf5::Datatype::from_type::<T>()?;
+    let ptr = array.as_ptr();
+
+    transfer_hyperslab(
+        dataset,
+        start,
+        array.shape(),
+        |mspace, fspace, dxpl| unsafe {
+            H5Dwrite(dataset.id(), dtype.id(), mspace, fspace, dxpl, ptr.cast())
+        },
+    )
+}
+
+fn read_hyperslab<T: H5Type>(
+    dataset: &Dataset,
+    start: &[usize],
+    count: &[usize],
+) -> hdf5::Result<ArrayD<T>> {
+    let dtype = hdf5::Datatype::from_type::<T>()?;
+    let num_elements: usize = count.iter().product();
+    let mut buffer: Vec<T> = Vec::with_capacity(num_elements);
+    let ptr = buffer.as_mut_ptr();
+
+    transfer_hyperslab(dataset, start, count, |mspace, fspace, dxpl| unsafe {
+        H5Dread(dataset.id(), dtype.id(), mspace, fspace, dxpl, ptr.cast())
+    })?;
+
+    // SAFETY: the read succeeded, so all elements of the buffer have been initialized
+    unsafe { buffer.set_len(num_elements) };
+
+    Ok(Array::from_shape_vec(IxDyn(count), buffer).expect("buffer length matches the selection"))
+}
+
+/// serializes the raw HDF5 calls made by this module. The HDF5 library is usually built without thread
+/// safety when parallel support is enabled
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// select the hyperslab `start`, `count` in the file space of `dataset`, and run `transfer`
+/// with the memory space, file space and a collective transfer property list
+fn transfer_hyperslab<F>(
+    dataset: &Dataset,
+    start: &[usize],
+    count: &[usize],
+    transfer: F,
+) -> hdf5::Result<()>
+where
+    F: FnOnce(hid_t, hid_t, hid_t) -> herr_t,
+{
+    let start: Vec<hsize_t> = start.iter().map(|&x| x as hsize_t).collect();
+    let count: Vec<hsize_t> = count.iter().map(|&x| x as hsize_t).collect();
+
+    // a panic while holding the lock leaves no HDF5 state behind that the next call depends on
+    let _lock = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+    unsafe {
+        // initializes the library, and the property list classes read below
+        check(H5open(), "H5open")?;
+
+        let mspace = RawHandle::new(
+            H5Screate_simple(count.len() as _, count.as_ptr(), std::ptr::null()),
+            H5Sclose,
+            "H5Screate_simple",
+        )?;
+        let fspace = RawHandle::new(H5Dget_space(dataset.id()), H5Sclose, "H5Dget_space")?;
+
+        // every rank must take part in a collective transfer, even if it has no data
+        if count.contains(&0) {
+            check(H5Sselect_none(mspace.id), "H5Sselect_none")?;
+            check(H5Sselect_none(fspace.id), "H5Sselect_none")?;
+        } else {
+            check(
+                H5Sselect_hyperslab(
+                    fspace.id,
+                    H5S_seloper_t::H5S_SELECT_SET,
+                    start.as_ptr(),
+                    std::ptr::null(),
+                    count.as_ptr(),
+                    std::ptr::null(),
+                ),
+                "H5Sselect_hyperslab",
+            )?;
+        }
+
+        let dxpl = RawHandle::new(H5Pcreate(*H5P_CLS_DATASET_XFER), H5Pclose, "H5Pcreate")?;
+        check(
+            H5Pset_dxpl_mpio(dxpl.id, H5FD_mpio_xfer_t::H5FD_MPIO_COLLECTIVE),
+            "H5Pset_dxpl_mpio",
+        )?;
+
+        check(
+            transfer(mspace.id, fspace.id, dxpl.id),
+            "collective transfer",
+        )?;
+    }
+
+    Ok(())
+}
+
+/// turn the negative return value of the HDF5 function `function` into an error
+fn check<T>(value: T, function: &str) -> hdf5::Result<T>
+where
+    T: PartialOrd + Default,
+{
+    if value < T::default() {
+        Err(format!("`{function}` failed").into())
+    } else {
+        Ok(value)
+    }
+}
+
+/// an HDF5 identifier that is closed when dropped
+struct RawHandle {
+    id: hid_t,
+    close: unsafe extern "C" fn(hid_t) -> herr_t,
+}
+
+impl RawHandle {
+    /// take ownership of `id`, returned by the HDF5 function `function`
+    fn new(
+        id: hid_t,
+        close: unsafe extern "C" fn(hid_t) -> herr_t,
+        function: &str,
+    ) -> hdf5::Result<Self> {
+        let id = check(id, function)?;
+        Ok(Self { id, close })
+    }
+}
+
+impl Drop for RawHandle {
+    fn drop(&mut self) {
+        unsafe {
+            (self.close)(self.id);
+        }
+    }
+}
@@ -0,0 +1,577 @@
+//! Progress reporting for long reads and writes
+//!
+//! An [`Observer`] installed with [`observe`] is called after each field of a derived container has been
+//! read or written, with the path of the field, the number of bytes of array and attribute data transferred, and the
+//! time it took. [`crate::LazyArray::read_chunked`] and [`crate::LazyArray::write_chunked`] transfer a single dataset
+//! in blocks along its leading axis, and report each block to [`Observer::chunk`].
+//!
+//! Returning [`ControlFlow::Break`] from either callback stops the operation cleanly: the field (or block)
+//! that was just reported is kept, and the read or write returns [`crate::Error::Cancelled`] before starting
+//! the next one.
+//!
+//! The observer is only active on the thread that called [`observe`]. To observe a read or write made by
+//! [`crate::AsyncWriter`] or the `async_io` wrappers, call [`observe`] inside the container's own method instead.
+//!
+//! ```
+//! use std::ops::ControlFlow;
+//! use hdf5_derive::{ContainerWrite};
+//! use hdf5_derive::progress::{observe, FieldProgress, Observer};
+//! use ndarray::Array2;
+//!
+//! #[derive(ContainerWrite)]
+//! struct Data {
+//!     pressure: Array2<f64>,
+//!     step: u64,
+//! }
+//!
+//! #[derive(Default)]
+//! struct Totals {
+//!     fields: Vec<String>,
+//!     bytes: u64,
+//! }
+//!
+//! impl Observer for Totals {
+//!     fn field(&mut self, progress: &FieldProgress) -> ControlFlow<()> {
+//!         self.fields.push(progress.path.to_string());
+//!         self.bytes += progress.bytes;
+//!         ControlFlow::Continue(())
+//!     }
+//! }
+//!
+//! let file = hdf5_derive::memory::create_in_memory().unwrap();
+//! let data = Data { pressure: Array2::zeros((10, 10)), step: 1 };
+//!
+//! let (result, totals) = observe(Totals::default(), || data.write_hdf5(&file));
+//! result.unwrap();
+//!
+//! assert_eq!(totals.fields, vec!["pressure", "step"]);
+//! assert_eq!(totals.bytes, 100 * 8 + 8);
+//! ```
+
+use std::any::Any;
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::ops::ControlFlow;
+use std::time::Duration;
+use std::time::Instant;
+
+use hdf5::Group;
+
+use crate::error;
+use crate::Error;
+
+/// Receives progress updates from reads and writes running inside [`observe`]
+pub trait Observer {
+    /// called after a field has been read or written. For a nested container, this is called after
+    /// each of its own fields, and then once for the container as a whole (with [`FieldProgress::nested`] set)
+    fn field(&mut self, progress: &FieldProgress) -> ControlFlow<()>;
+
+    /// called after each block of a [`crate::LazyArray::read_chunked`] or [`crate::LazyArray::write_chunked`]
+    fn chunk(&mut self, _progress: &ChunkProgress) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Whether data is being read from, or written to a file
+pub enum Operation {
+    /// data is read from the file
+    Read,
+    /// data is written to the file
+    Write,
+}
+
+#[derive(Debug, Clone)]
+/// A field that has finished reading or writing
+pub struct FieldProgress<'a> {
+    /// reading or writing
+    pub operation: Operation,
+    /// names of the field and the containers holding it, separated by `/`, starting from the group passed
+    /// to the outermost read or write
+    pub path: &'a str,
+    /// bytes of array and attribute data transferred for the field, including all fields of a nested container
+    pub bytes: u64,
+    /// time taken to read or write the field
+    pub elapsed: Duration,
+    /// whether or not the field is a nested container, whose fields have already been reported
+    pub nested: bool,
+}
+
+#[derive(Debug, Clone)]
+/// A block of a dataset that has finished reading or writing
+pub struct ChunkProgress<'a> {
+    /// reading or writing
+    pub operation: Operation,
+    /// name of the dataset
+    pub name: &'a str,
+    /// number of elements along the leading axis transferred so far
+    pub done: usize,
+    /// length of the leading axis
+    pub total: usize,
+    /// bytes transferred so far
+    pub bytes: u64,
+    /// time since the operation started
+    pub elapsed: Duration,
+}
+
+/// run `f` on the current thread, reporting the progress of derived reads and writes (and chunked
+/// [`crate::LazyArray`] operations) to `observer`. Returns the output of `f` along with `observer`
+///
+/// If `observer` returns [`ControlFlow::Break`], the next field or block returns [`Error::Cancelled`]. Work that
+/// finishes without starting another field is not affected.
+pub fn observe<O: Observer + 'static, R>(observer: O, f: impl FnOnce() -> R) -> (R, O) {
+    let previous = STATE.with(|state| {
+        state.replace(Some(State {
+            observer: Some(Box::new(observer)),
+            path: Vec::new(),
+            reported: 0,
+            stop: false,
+        }))
+    });
+    let restore = Restore {
+        previous: Some(previous),
+    };
+
+    let output = f();
+
+    let observer = restore
+        .finish()
+        .and_then(|state| state.observer)
+        .expect("the observer is put back after every call")
+        .into_any()
+        .downcast::<O>()
+        .expect("the observer installed by `observe` is still active");
+
+    (output, *observer)
+}
+
+thread_local! {
+    static STATE: RefCell<Option<State>> = const { RefCell::new(None) };
+    static BYTES: Cell<u64> = const { Cell::new(0) };
+}
+
+/// an [`Observer`] that can be moved back out of the thread local once [`observe`] is done
+trait ActiveObserver {
+    fn observer(&mut self) -> &mut dyn Observer;
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<O: Observer + 'static> ActiveObserver for O {
+    fn observer(&mut self) -> &mut dyn Observer {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// the observer of the innermost [`observe`] on this thread
+struct State {
+    /// taken while the observer is being called, so that reads made by the observer itself are not reported
+    observer: Option<Box<dyn ActiveObserver>>,
+    /// names of the fields currently being read or written
+    path: Vec<String>,
+    /// number of fields reported so far, used to tell nested containers apart from arrays and attributes
+    reported: usize,
+    /// set once the observer has returned `Break`
+    stop: bool,
+}
+
+/// puts back the state that was active before [`observe`], even if `f` panics
+struct Restore {
+    /// `None` once [`Restore::finish`] has run
+    previous: Option<Option<State>>,
+}
+
+impl Restore {
+    /// put back the previous state, returning the state of this [`observe`]
+    fn finish(mut self) -> Option<State> {
+        let previous = self.previous.take().flatten();
+        STATE.with(|state| state.replace(previous))
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            STATE.with(|state| *state.borrow_mut() = previous);
+        }
+    }
+}
+
+/// count `bytes` of data as transferred by the current field. Called by the implementations that move
+/// array and attribute data
+pub(crate) fn record_bytes(bytes: usize) {
+    BYTES.with(|total| total.set(total.get().wrapping_add(bytes as u64)));
+}
+
+pub(crate) fn recorded_bytes() -> u64 {
+    BYTES.with(Cell::get)
+}
+
+/// call the active observer with `call`, remembering if it asks to stop
+fn notify(call: impl FnOnce(&mut dyn Observer) -> ControlFlow<()>) {
+    let observer = STATE.with(|state| {
+        state
+            .borrow_mut()
+            .as_mut()
+            .and_then(|state| state.observer.take())
+    });
+
+    let mut observer = match observer {
+        Some(observer) => observer,
+        None => return,
+    };
+
+    let flow = call(observer.observer());
+
+    STATE.with(|state| {
+        if let Some(state) = state.borrow_mut().as_mut() {
+            state.observer = Some(observer);
+            state.stop |= flow.is_break();
+        }
+    });
+}
+
+/// whether or not the observer has asked to stop
+fn stopped() -> bool {
+    STATE.with(|state| state.borrow().as_ref().is_some_and(|state| state.stop))
+}
+
+/// reads or writes a single field of a container with `f`, checking for cancellation first and
+/// reporting it to the active observer afterwards. Errors are tagged with the location of the field
+/// (see [`crate::error::ErrorContext`]). Called by derived code
+///
+/// `name` is the name of the field in the file, and `rust_field` the name of the struct field.
+#[doc(hidden)]
+pub fn field<R>(
+    group: &Group,
+    name: &str,
+    rust_field: &str,
+    operation: Operation,
+    f: impl FnOnce() -> Result<R, Error>,
+) -> Result<R, Error> {
+    observed_field(group, name, operation, f).map_err(|mut e| {
+        e.context_mut().push_field(group, name, rust_field);
+        e
+    })
+}
+
+/// like [`field`], for reads that collect every error instead of stopping at the first one. Errors are
+/// added to `errors` and `None` is returned, and the errors of a nested container read with
+/// [`crate::ContainerRead::read_hdf5_report`] are added one by one. Cancellation is still returned
+/// straight away. Called by derived code
+#[doc(hidden)]
+pub fn report_field<R>(
+    errors: &mut Vec<Error>,
+    group: &Group,
+    name: &str,
+    rust_field: &str,
+    f: impl FnOnce() -> Result<R, Error>,
+) -> Result<Option<R>, Error> {
+    match field(group, name, rust_field, Operation::Read, f) {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::Cancelled(e)) => Err(e.into()),
+        Err(Error::Aggregate(aggregate)) => {
+            errors.extend(aggregate.into_errors().into_iter().map(|mut e| {
+                e.context_mut().push_field(group, name, rust_field);
+                e
+            }));
+
+            Ok(None)
+        }
+        Err(e) => {
+            errors.push(e);
+            Ok(None)
+        }
+    }
+}
+
+fn observed_field<R>(
+    group: &Group,
+    name: &str,
+    operation: Operation,
+    f: impl FnOnce() -> Result<R, Error>,
+) -> Result<R, Error> {
+    crate::cancel::check_cancelled(name)?;
+
+    if stopped() {
+        return Err(error::Cancelled::new(name).into());
+    }
+
+    #[cfg(feature = "tracing")]
+    let _span = crate::instrument::FieldSpan::enter(group, name, operation);
+    #[cfg(not(feature = "tracing"))]
+    let _ = group;
+
+    let reported = STATE.with(|state| {
+        state.borrow_mut().as_mut().map(|state| {
+            state.path.push(name.to_string());
+            state.reported
+        })
+    });
+
+    let reported_before = match reported {
+        Some(reported) => reported,
+        // nothing is observing this thread
+        None => return f(),
+    };
+
+    let bytes_before = recorded_bytes();
+    let start = Instant::now();
+
+    let output = f();
+
+    let elapsed = start.elapsed();
+    let bytes = recorded_bytes().wrapping_sub(bytes_before);
+
+    let (path, nested) = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let state = state
+            .as_mut()
+            .expect("the observer is active until `observe` returns");
+
+        let path = state.path.join("/");
+        state.path.pop();
+
+        let nested = state.reported > reported_before;
+        state.reported += 1;
+
+        (path, nested)
+    });
+
+    let output = output?;
+
+    let progress = FieldProgress {
+        operation,
+        path: &path,
+        bytes,
+        elapsed,
+        nested,
+    };
+
+    // a break stops the next field, the value of this one is complete
+    notify(|observer| observer.field(&progress));
+
+    Ok(output)
+}
+
+/// reports the blocks of a chunked [`crate::LazyArray`] operation
+pub(crate) struct ChunkTracker<'a> {
+    operation: Operation,
+    name: &'a str,
+    total: usize,
+    bytes: u64,
+    start: Instant,
+}
+
+impl<'a> ChunkTracker<'a> {
+    pub(crate) fn new(operation: Operation, name: &'a str, total: usize) -> Self {
+        Self {
+            operation,
+            name,
+            total,
+            bytes: 0,
+            start: Instant::now(),
+        }
+    }
+
+    /// check for cancellation before transferring the next block
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        crate::cancel::check_cancelled(self.name)?;
+
+        if stopped() {
+            return Err(error::Cancelled::new(self.name).into());
+        }
+
+        Ok(())
+    }
+
+    /// record that the leading axis has been transferred up to `done`, with `bytes` in the latest block
+    pub(crate) fn advance(&mut self, done: usize, bytes: usize) {
+        record_bytes(bytes);
+        self.bytes += bytes as u64;
+
+        let progress = ChunkProgress {
+            operation: self.operation,
+            name: self.name,
+            done,
+            total: self.total,
+            bytes: self.bytes,
+            elapsed: self.start.elapsed(),
+        };
+
+        // a break is picked up by `check` before the next block
+        notify(|observer| observer.chunk(&progress));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContainerRead, ContainerWrite};
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::{Array1, Array2};
+
+    #[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+    struct Nested {
+        energy: Array1<f32>,
+    }
+
+    #[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+    struct Data {
+        velocity: Array2<f64>,
+        nested: Nested,
+        step: u32,
+    }
+
+    /// records every field, and breaks after `break_after` fields
+    #[derive(Default)]
+    struct Recorder {
+        fields: Vec<(Operation, String, u64, bool)>,
+        chunks: Vec<(usize, usize)>,
+        break_after: Option<usize>,
+    }
+
+    impl Observer for Recorder {
+        fn field(&mut self, progress: &FieldProgress) -> ControlFlow<()> {
+            self.fields.push((
+                progress.operation,
+                progress.path.to_string(),
+                progress.bytes,
+                progress.nested,
+            ));
+
+            match self.break_after {
+                Some(count) if self.fields.len() >= count => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        }
+
+        fn chunk(&mut self, progress: &ChunkProgress) -> ControlFlow<()> {
+            self.chunks.push((progress.done, progress.total));
+
+            match self.break_after {
+                Some(count) if self.chunks.len() >= count => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        }
+    }
+
+    #[test]
+    fn reports_fields() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        let data = Data {
+            velocity: Array2::zeros((4, 5)),
+            nested: Nested {
+                energy: Array1::zeros(3),
+            },
+            step: 9,
+        };
+
+        let (result, recorder) = observe(Recorder::default(), || data.write_hdf5(&file));
+        result.unwrap();
+
+        let write = Operation::Write;
+        assert_eq!(
+            recorder.fields,
+            vec![
+                (write, "velocity".to_string(), 20 * 8, false),
+                (write, "nested/energy".to_string(), 3 * 4, false),
+                (write, "nested".to_string(), 3 * 4, true),
+                (write, "step".to_string(), 4, false),
+            ]
+        );
+
+        let (read, recorder) = observe(Recorder::default(), || Data::read_hdf5(&file));
+
+        assert_eq!(read.unwrap(), data);
+        assert_eq!(recorder.fields.len(), 4);
+        assert!(recorder
+            .fields
+            .iter()
+            .all(|field| field.0 == Operation::Read));
+        assert_eq!(recorder.fields[0].2, 20 * 8);
+    }
+
+    #[test]
+    fn break_stops_next_field() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        let data = Data {
+            velocity: Array2::zeros((4, 5)),
+            nested: Nested {
+                energy: Array1::zeros(3),
+            },
+            step: 9,
+        };
+
+        let recorder = Recorder {
+            break_after: Some(1),
+            ..Default::default()
+        };
+
+        let (result, recorder) = observe(recorder, || data.write_hdf5(&file));
+
+        assert!(matches!(result, Err(Error::Cancelled(_))));
+        assert_eq!(recorder.fields.len(), 1);
+        assert!(file.link_exists("velocity"));
+        assert!(!file.link_exists("nested"));
+    }
+
+    #[test]
+    fn failed_write_records_no_bytes() {
+        let file = crate::memory::create_in_memory().unwrap();
+        file.new_dataset::<f64>()
+            .shape((4, 5))
+            .create("velocity")
+            .unwrap();
+
+        let data = Data {
+            velocity: Array2::zeros((4, 5)),
+            nested: Nested {
+                energy: Array1::zeros(3),
+            },
+            step: 9,
+        };
+
+        // `velocity` already exists, so creating it fails before any data is moved
+        let before = recorded_bytes();
+        assert!(data.write_hdf5(&file).is_err());
+        assert_eq!(recorded_bytes(), before);
+    }
+
+    #[test]
+    fn reports_chunks() {
+        let file = crate::memory::create_in_memory().unwrap();
+        let arr = Array2::from_shape_fn((10, 3), |(i, j)| (i * 3 + j) as f64);
+
+        let dataset = file
+            .new_dataset::<f64>()
+            .shape((10, 3))
+            .create("arr")
+            .unwrap();
+        let lazy = crate::LazyArray2::<f64>::new(dataset).unwrap();
+
+        let (result, recorder) = observe(Recorder::default(), || lazy.write_chunked(arr.view(), 4));
+        result.unwrap();
+        assert_eq!(recorder.chunks, vec![(4, 10), (8, 10), (10, 10)]);
+
+        let (read, recorder) = observe(Recorder::default(), || lazy.read_chunked(3));
+        assert_eq!(read.unwrap(), arr);
+        assert_eq!(recorder.chunks.len(), 4);
+
+        let recorder = Recorder {
+            break_after: Some(2),
+            ..Default::default()
+        };
+        let (result, recorder) = observe(recorder, || lazy.read_chunked(3));
+        assert!(matches!(result, Err(Error::Cancelled(_))));
+        assert_eq!(recorder.chunks.len(), 2);
+    }
+}
@@ -0,0 +1,211 @@
+//! Storing [`uom`] quantities in SI base units
+//!
+//! Requires the `uom` feature.
+//!
+//! Fields marked with `#[hdf5(uom)]` hold a `uom` quantity in SI units, such as `uom::si::f64::Length`,
+//! or an array of them, such as `Array3<Velocity>`. They are stored as plain numbers in SI base units
+//! (meters, kilograms, seconds, amperes, kelvin, moles and candelas), with a `units` attribute spelling out
+//! the base units in [UDUNITS](https://docs.unidata.ucar.edu/udunits/current/) syntax, such as `m s-1` for
+//! a velocity. Other codes reading the file do not need to know anything about `uom` to interpret the data.
+//!
+//! `check_units` works the same as for other fields, comparing the `units` attribute in the file against the
+//! units of the quantity:
+//!
+//! ```
+//! use hdf5_derive::{ContainerRead, ContainerWrite};
+//! use hdf5_derive::metadata::{string_attribute, UNITS};
+//! use ndarray::Array1;
+//! use uom::si::f64::{Length, Velocity};
+//! use uom::si::length::kilometer;
+//! use uom::si::velocity::meter_per_second;
+//!
+//! #[derive(ContainerRead, ContainerWrite)]
+//! #[hdf5(check_units)]
+//! struct Flow {
+//!     #[hdf5(uom)]
+//!     velocity: Array1<Velocity>,
+//!     #[hdf5(uom)]
+//!     domain_length: Length,
+//! }
+//!
+//! let file = hdf5_derive::memory::create_in_memory().unwrap();
+//!
+//! let flow = Flow {
+//!     velocity: Array1::from_elem(3, Velocity::new::<meter_per_second>(2.)),
+//!     domain_length: Length::new::<kilometer>(1.5),
+//! };
+//! flow.write_hdf5(&file).unwrap();
+//!
+//! // stored in SI base units
+//! let velocity = file.dataset("velocity").unwrap();
+//! assert_eq!(string_attribute(&velocity, UNITS).unwrap(), "m s-1");
+//! let length: f64 = file.attr("domain_length").unwrap().read_scalar().unwrap();
+//! assert_eq!(length, 1500.);
+//!
+//! let read = Flow::read_hdf5(&file).unwrap();
+//! assert_eq!(read.domain_length, flow.domain_length);
+//! ```
+
+use ndarray::Array;
+use ndarray::ArrayBase;
+use ndarray::Dimension;
+use ndarray::OwnedRepr;
+
+use ::uom::si::Quantity;
+use ::uom::si::SI;
+use ::uom::typenum::Integer;
+use ::uom::Conversion;
+
+/// Converts a quantity, or an array of quantities, to and from the numbers stored in the file. You likely
+/// do not want to use this trait; instead mark fields with `#[hdf5(uom)]`
+pub trait Quantities {
+    /// the type stored in the file, in SI base units
+    type Stored;
+
+    /// the SI base units of the quantity, such as `m s-1`
+    fn units() -> String;
+
+    /// the value in SI base units
+    fn to_stored(&self) -> Self::Stored;
+
+    /// construct from a value in SI base units
+    fn from_stored(stored: Self::Stored) -> Self;
+}
+
+impl<D, V> Quantities for Quantity<D, SI<V>, V>
+where
+    D: ::uom::si::Dimension + ?Sized,
+    SI<V>: ::uom::si::Units<V>,
+    V: ::uom::num::Num + Conversion<V> + Copy,
+{
+    type Stored = V;
+
+    fn units() -> String {
+        units::<D>()
+    }
+
+    fn to_stored(&self) -> V {
+        // `uom` keeps quantities in the base units of the system, which are the SI base units
+        self.value
+    }
+
+    fn from_stored(stored: V) -> Self {
+        Quantity {
+            dimension: std::marker::PhantomData,
+            units: std::marker::PhantomData,
+            value: stored,
+        }
+    }
+}
+
+impl<D, V, DIM> Quantities for ArrayBase<OwnedRepr<Quantity<D, SI<V>, V>>, DIM>
+where
+    D: ::uom::si::Dimension + ?Sized,
+    SI<V>: ::uom::si::Units<V>,
+    V: ::uom::num::Num + Conversion<V> + Copy,
+    DIM: Dimension,
+{
+    type Stored = Array<V, DIM>;
+
+    fn units() -> String {
+        units::<D>()
+    }
+
+    fn to_stored(&self) -> Self::Stored {
+        self.map(|quantity| quantity.value)
+    }
+
+    fn from_stored(stored: Self::Stored) -> Self {
+        stored.mapv(Quantity::<D, SI<V>, V>::from_stored)
+    }
+}
+
+/// the SI base units of the dimension `D` in UDUNITS syntax, such as `kg m-3` or `1` for dimensionless quantities.
+/// Units are listed in the order kg, m, s, A, K, mol, cd
+pub fn units<D>() -> String
+where
+    D: ::uom::si::Dimension + ?Sized,
+{
+    let exponents = [
+        ("kg", D::M::to_i64()),
+        ("m", D::L::to_i64()),
+        ("s", D::T::to_i64()),
+        ("A", D::I::to_i64()),
+        ("K", D::Th::to_i64()),
+        ("mol", D::N::to_i64()),
+        ("cd", D::J::to_i64()),
+    ];
+
+    let units: Vec<String> = exponents
+        .iter()
+        .filter(|(_, exponent)| *exponent != 0)
+        .map(|(symbol, exponent)| match exponent {
+            1 => symbol.to_string(),
+            exponent => format!("{symbol}{exponent}"),
+        })
+        .collect();
+
+    if units.is_empty() {
+        "1".into()
+    } else {
+        units.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::{string_attribute, UNITS};
+    use crate::{ContainerRead, ContainerWrite};
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ::uom::si::f64::{Length, MassDensity, Ratio, Velocity};
+    use ::uom::si::length::millimeter;
+    use ::uom::si::velocity::kilometer_per_hour;
+    use ndarray::Array2;
+
+    #[derive(ContainerRead, ContainerWrite)]
+    struct Data {
+        #[hdf5(uom, transpose = "both")]
+        velocity: Array2<Velocity>,
+        #[hdf5(uom)]
+        length: Length,
+    }
+
+    #[test]
+    fn base_units() {
+        assert_eq!(<Velocity as Quantities>::units(), "m s-1");
+        assert_eq!(<MassDensity as Quantities>::units(), "kg m-3");
+        assert_eq!(<Ratio as Quantities>::units(), "1");
+    }
+
+    #[test]
+    fn round_trip() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        let data = Data {
+            velocity: Array2::from_shape_fn((2, 3), |(i, j)| {
+                Velocity::new::<kilometer_per_hour>((i * 3 + j) as f64)
+            }),
+            length: Length::new::<millimeter>(5.),
+        };
+
+        data.write_hdf5(&file).unwrap();
+
+        let velocity = file.dataset("velocity").unwrap();
+        assert_eq!(string_attribute(&velocity, UNITS).unwrap(), "m s-1");
+
+        // stored transposed, in m/s
+        let stored: Array2<f64> = velocity.read().unwrap();
+        assert_eq!(stored.shape(), [3, 2]);
+        assert!((stored[[1, 0]] - 1. / 3.6).abs() < 1e-12);
+
+        // scalars are attributes, so their units are a sibling attribute
+        assert_eq!(string_attribute(&file, "length_units").unwrap(), "m");
+
+        let read = Data::read_hdf5(&file).unwrap();
+        assert_eq!(read.velocity, data.velocity);
+        assert_eq!(read.length, data.length);
+    }
+}
@@ -0,0 +1,826 @@
+//! Static descriptions of the layout of derived containers
+//!
+//! [`ContainerSchema`], derived with the `ContainerSchema` proc macro, describes every field of a container
+//! without needing a file: its Rust name, the names it is read from and written to, the kind of object backing
+//! it, its element type and rank, the `transpose` / `mutate_on_write` / `select` flags, and the schema of any
+//! nested container. Tools that need to know the file format, such as docs generators or loaders in other
+//! languages, can be driven from the struct definition:
+//!
+//! ```
+//! use hdf5_derive::{ContainerRead, ContainerSchema};
+//! use hdf5_derive::validate::ObjectKind;
+//! use ndarray::{Array1, Array3};
+//!
+//! #[derive(ContainerRead, ContainerSchema)]
+//! struct Grid {
+//!     x: Array1<f64>,
+//! }
+//!
+//! #[derive(ContainerRead, ContainerSchema)]
+//! struct Solution {
+//!     #[hdf5(transpose = "read", rename(read = "u"))]
+//!     velocity: Array3<f32>,
+//!     step: u64,
+//!     grid: Grid,
+//! }
+//!
+//! let schema = Solution::schema();
+//! assert_eq!(schema.name, "Solution");
+//!
+//! let velocity = &schema.fields[0];
+//! assert_eq!((velocity.read_name, velocity.write_name), ("u", "velocity"));
+//! assert_eq!(velocity.layout.object, ObjectKind::Dataset);
+//! assert_eq!(velocity.layout.rank, Some(3));
+//! assert!(velocity.transpose_read && !velocity.transpose_write);
+//!
+//! let grid = schema.fields[2].layout.nested.as_ref().unwrap();
+//! assert_eq!(grid.fields[0].rust_name, "x");
+//! ```
+
+use hdf5::types::{TypeDescriptor, VarLenUnicode};
+use hdf5::H5Type;
+
+use crate::validate::ObjectKind;
+
+/// Provides a static description of the fields of a container. Derived with the
+/// [`ContainerSchema`](macro@crate::ContainerSchema) proc macro.
+pub trait ContainerSchema {
+    /// describe the layout of `Self` in a file
+    fn schema() -> Schema;
+
+    /// describe the layout of `Self` in a file as JSON. See [`Schema::to_json`]
+    fn schema_json() -> String
+    where
+        Self: Sized,
+    {
+        Self::schema().to_json()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The layout of a container, returned by [`ContainerSchema::schema`]
+pub struct Schema {
+    /// name of the Rust struct
+    pub name: &'static str,
+    /// `///` doc comments on the struct, without the leading `///`
+    pub docs: &'static str,
+    /// whether or not the container is written with a `write_complete` marker
+    pub write_complete: bool,
+    /// every field of the struct, in declaration order
+    pub fields: Vec<Field>,
+}
+
+impl Schema {
+    /// the field with the Rust name `rust_name`
+    pub fn field(&self, rust_name: &str) -> Option<&Field> {
+        self.fields
+            .iter()
+            .find(|field| field.rust_name == rust_name)
+    }
+
+    /// a machine readable description of the layout, for tools written in other languages
+    ///
+    /// The output is pretty printed JSON with a fixed key order, so it can be checked into a repository and
+    /// diffed. The top level object holds `"format": "hdf5-derive-schema"`, the format `"version"` (currently
+    /// `1`, and only changed when existing keys change meaning), and the `"root"` group. Each group has
+    /// its `name`, `docs`, `write_complete` flag and `fields`. Each field has
+    ///
+    /// * `rust_name`, `read_name`, `write_name` and `docs`
+    /// * `object`: `"dataset"`, `"attribute"` or `"group"`
+    /// * `lazy`: whether the field is only read when accessed
+    /// * `dtype`: the element type, or `null` for groups (see below)
+    /// * `rank`: the number of dimensions (`0` for scalar attributes), or `null` for groups and dynamically
+    ///   sized arrays
+    /// * `units`: the units written to the `units` attribute, or `null`
+    /// * `attributes`: the attributes written on the object by `attrs(..)` or [`crate::WithAttrs`], each with its
+    ///   `name`, `dtype` and literal `value` (`null` unless given in `attrs(..)`)
+    /// * `transpose_read`, `transpose_write`, `mutate_on_write` and `select`
+    /// * `group`: the nested group, or `null`
+    ///
+    /// Each `dtype` is an object with a `kind` and its `size` in bytes (`null` for variable length types):
+    ///
+    /// * `"int"`, `"uint"`, `"float"` and `"bool"`
+    /// * `"enum"`, with whether it is `signed` and its `members`, each with a `name` and `value`
+    /// * `"compound"`, with its `fields`, each with a `name`, byte `offset` and `dtype`
+    /// * `"array"`, with its `length` (`null` for variable length arrays) and the `element` dtype
+    /// * `"string"`, with its `encoding`: `"ascii"` or `"utf-8"`
+    ///
+    /// ```
+    /// use hdf5_derive::ContainerSchema;
+    /// use ndarray::Array2;
+    ///
+    /// #[derive(ContainerSchema)]
+    /// /// a snapshot of the flow
+    /// struct Snapshot {
+    ///     /// streamwise velocity
+    ///     velocity: Array2<f32>,
+    /// }
+    ///
+    /// let json = Snapshot::schema_json();
+    ///
+    /// assert!(json.contains(r#""docs": "streamwise velocity""#));
+    /// assert!(json.contains(r#""kind": "float""#));
+    /// assert!(json.contains(r#""size": 4"#));
+    /// assert!(json.contains(r#""rank": 2"#));
+    /// ```
+    pub fn to_json(&self) -> String {
+        let document = Json::Object(vec![
+            ("format", Json::String("hdf5-derive-schema".into())),
+            ("version", Json::Number(JSON_VERSION)),
+            ("root", self.json()),
+        ]);
+
+        let mut output = String::new();
+        document.write(&mut output, 0);
+        output.push('\n');
+
+        output
+    }
+
+    fn json(&self) -> Json {
+        Json::Object(vec![
+            ("name", Json::String(self.name.into())),
+            ("docs", Json::String(self.docs.into())),
+            ("write_complete", Json::Bool(self.write_complete)),
+            (
+                "fields",
+                Json::Array(self.fields.iter().map(Field::json).collect()),
+            ),
+        ])
+    }
+}
+
+impl Field {
+    fn json(&self) -> Json {
+        let layout = &self.layout;
+
+        Json::Object(vec![
+            ("rust_name", Json::String(self.rust_name.into())),
+            ("read_name", Json::String(self.read_name.into())),
+            ("write_name", Json::String(self.write_name.into())),
+            ("docs", Json::String(self.docs.into())),
+            ("object", Json::String(layout.object.to_string())),
+            ("lazy", Json::Bool(layout.lazy)),
+            (
+                "dtype",
+                layout.element_type.as_ref().map_or(Json::Null, dtype_json),
+            ),
+            (
+                "rank",
+                layout
+                    .rank
+                    .map_or(Json::Null, |rank| Json::Number(rank as u64)),
+            ),
+            (
+                "units",
+                self.units
+                    .as_ref()
+                    .map_or(Json::Null, |units| Json::String(units.clone())),
+            ),
+            (
+                "attributes",
+                Json::Array(layout.attributes.iter().map(Attribute::json).collect()),
+            ),
+            ("transpose_read", Json::Bool(self.transpose_read)),
+            ("transpose_write", Json::Bool(self.transpose_write)),
+            ("mutate_on_write", Json::Bool(self.mutate_on_write)),
+            ("select", Json::Bool(self.select)),
+            (
+                "group",
+                layout.nested.as_ref().map_or(Json::Null, Schema::json),
+            ),
+        ])
+    }
+}
+
+impl Attribute {
+    fn json(&self) -> Json {
+        let value = match &self.value {
+            Some(AttributeLiteral::String(value)) => Json::String((*value).into()),
+            Some(AttributeLiteral::Integer(value)) => Json::Integer(*value),
+            Some(AttributeLiteral::Float(value)) => Json::Float(*value),
+            None => Json::Null,
+        };
+
+        Json::Object(vec![
+            ("name", Json::String(self.name.into())),
+            ("dtype", dtype_json(&self.element_type)),
+            ("value", value),
+        ])
+    }
+}
+
+/// describe an element type as a JSON object with its `kind` and `size` in bytes, independent of how `hdf5`
+/// formats the type
+fn dtype_json(dtype: &TypeDescriptor) -> Json {
+    let kind = |kind: &str, size: Option<usize>| {
+        vec![
+            ("kind", Json::String(kind.into())),
+            (
+                "size",
+                size.map_or(Json::Null, |size| Json::Number(size as u64)),
+            ),
+        ]
+    };
+
+    let entries = match dtype {
+        TypeDescriptor::Integer(size) => kind("int", Some(*size as usize)),
+        TypeDescriptor::Unsigned(size) => kind("uint", Some(*size as usize)),
+        TypeDescriptor::Float(size) => kind("float", Some(*size as usize)),
+        TypeDescriptor::Boolean => kind("bool", Some(dtype.size())),
+        TypeDescriptor::Enum(enum_type) => {
+            let members = enum_type
+                .members
+                .iter()
+                .map(|member| {
+                    Json::Object(vec![
+                        ("name", Json::String(member.name.clone())),
+                        ("value", Json::Number(member.value)),
+                    ])
+                })
+                .collect();
+
+            let mut entries = kind("enum", Some(enum_type.size as usize));
+            entries.push(("signed", Json::Bool(enum_type.signed)));
+            entries.push(("members", Json::Array(members)));
+            entries
+        }
+        TypeDescriptor::Compound(compound) => {
+            let fields = compound
+                .fields
+                .iter()
+                .map(|field| {
+                    Json::Object(vec![
+                        ("name", Json::String(field.name.clone())),
+                        ("offset", Json::Number(field.offset as u64)),
+                        ("dtype", dtype_json(&field.ty)),
+                    ])
+                })
+                .collect();
+
+            let mut entries = kind("compound", Some(compound.size));
+            entries.push(("fields", Json::Array(fields)));
+            entries
+        }
+        TypeDescriptor::FixedArray(element, length) => {
+            let mut entries = kind("array", Some(dtype.size()));
+            entries.push(("length", Json::Number(*length as u64)));
+            entries.push(("element", dtype_json(element)));
+            entries
+        }
+        TypeDescriptor::VarLenArray(element) => {
+            let mut entries = kind("array", None);
+            entries.push(("length", Json::Null));
+            entries.push(("element", dtype_json(element)));
+            entries
+        }
+        TypeDescriptor::FixedAscii(size) => string_kind(kind("string", Some(*size)), "ascii"),
+        TypeDescriptor::FixedUnicode(size) => string_kind(kind("string", Some(*size)), "utf-8"),
+        TypeDescriptor::VarLenAscii => string_kind(kind("string", None), "ascii"),
+        TypeDescriptor::VarLenUnicode => string_kind(kind("string", None), "utf-8"),
+    };
+
+    Json::Object(entries)
+}
+
+/// add the `encoding` of a string dtype
+fn string_kind(
+    mut entries: Vec<(&'static str, Json)>,
+    encoding: &str,
+) -> Vec<(&'static str, Json)> {
+    entries.push(("encoding", Json::String(encoding.into())));
+    entries
+}
+
+/// version of the output of [`Schema::to_json`]
+const JSON_VERSION: u64 = 1;
+
+/// the subset of JSON needed to describe a schema, with object keys in insertion order
+enum Json {
+    Null,
+    Bool(bool),
+    Number(u64),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    /// pretty print `self` to `output`, with nested values indented by two spaces per `level`
+    fn write(&self, output: &mut String, level: usize) {
+        let indent = "  ".repeat(level + 1);
+        let closing_indent = "  ".repeat(level);
+
+        match self {
+            Json::Null => output.push_str("null"),
+            Json::Bool(value) => output.push_str(if *value { "true" } else { "false" }),
+            Json::Number(value) => output.push_str(&value.to_string()),
+            Json::Integer(value) => output.push_str(&value.to_string()),
+            // `Debug` keeps the decimal point of whole numbers
+            Json::Float(value) => output.push_str(&format!("{value:?}")),
+            Json::String(value) => write_json_string(output, value),
+            Json::Array(values) if values.is_empty() => output.push_str("[]"),
+            Json::Array(values) => {
+                output.push_str("[\n");
+
+                for (index, value) in values.iter().enumerate() {
+                    output.push_str(&indent);
+                    value.write(output, level + 1);
+                    output.push_str(if index + 1 < values.len() {
+                        ",\n"
+                    } else {
+                        "\n"
+                    });
+                }
+
+                output.push_str(&closing_indent);
+                output.push(']');
+            }
+            Json::Object(entries) => {
+                output.push_str("{\n");
+
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    output.push_str(&indent);
+                    write_json_string(output, key);
+                    output.push_str(": ");
+                    value.write(output, level + 1);
+                    output.push_str(if index + 1 < entries.len() {
+                        ",\n"
+                    } else {
+                        "\n"
+                    });
+                }
+
+                output.push_str(&closing_indent);
+                output.push('}');
+            }
+        }
+    }
+}
+
+/// write `value` as a quoted JSON string, escaping quotes, backslashes and control characters
+fn write_json_string(output: &mut String, value: &str) {
+    output.push('"');
+
+    for character in value.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            '\r' => output.push_str("\\r"),
+            character if (character as u32) < 0x20 => {
+                output.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => output.push(character),
+        }
+    }
+
+    output.push('"');
+}
+
+#[derive(Debug, Clone)]
+/// A single field of a [`Schema`]
+pub struct Field {
+    /// name of the struct field
+    pub rust_name: &'static str,
+    /// name of the object the field is read from
+    pub read_name: &'static str,
+    /// name of the object the field is written to
+    pub write_name: &'static str,
+    /// `///` doc comments on the field, without the leading `///`
+    pub docs: &'static str,
+    /// what the field is stored as
+    pub layout: Layout,
+    /// units written to the `units` attribute, from `#[hdf5(units = "..")]` or the SI base units of `uom` fields
+    pub units: Option<String>,
+    /// whether or not the array is transposed when read
+    pub transpose_read: bool,
+    /// whether or not the array is transposed when written
+    pub transpose_write: bool,
+    /// whether or not an existing object is overwritten in place when written
+    pub mutate_on_write: bool,
+    /// whether or not the field is restricted to the selection of `read_hdf5_selection` / `write_hdf5_selection`
+    pub select: bool,
+}
+
+#[derive(Debug, Clone)]
+/// What a field type is stored as, returned by [`FieldLayout::layout`]
+pub struct Layout {
+    /// the kind of object backing the field
+    pub object: ObjectKind,
+    /// whether or not the field is only read when accessed, such as a [`crate::LazyArray`] or [`crate::Lazy`]
+    pub lazy: bool,
+    /// element type of a dataset or attribute. `None` for groups
+    pub element_type: Option<TypeDescriptor>,
+    /// number of dimensions of a dataset or attribute. `None` for groups and dynamically sized arrays
+    pub rank: Option<usize>,
+    /// schema of a nested container
+    pub nested: Option<Schema>,
+    /// attributes written on the dataset or group itself, from `#[hdf5(attrs(..))]` or the metadata of a
+    /// [`crate::WithAttrs`]
+    pub attributes: Vec<Attribute>,
+}
+
+#[derive(Debug, Clone)]
+/// An attribute written on the dataset or group backing a field
+pub struct Attribute {
+    /// name of the attribute
+    pub name: &'static str,
+    /// element type of the attribute
+    pub element_type: TypeDescriptor,
+    /// the value of attributes given as literals in `#[hdf5(attrs(..))]`. `None` for the fields of a
+    /// [`crate::ContainerAttributes`] struct, which are only known when writing
+    pub value: Option<AttributeLiteral>,
+}
+
+impl Attribute {
+    /// describe the attribute `name` with the literal `value`
+    pub fn literal(name: &'static str, value: AttributeLiteral) -> Self {
+        let element_type = match value {
+            AttributeLiteral::String(_) => VarLenUnicode::type_descriptor(),
+            AttributeLiteral::Integer(_) => i64::type_descriptor(),
+            AttributeLiteral::Float(_) => f64::type_descriptor(),
+        };
+
+        Self {
+            name,
+            element_type,
+            value: Some(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The value of a literal attribute in `#[hdf5(attrs(..))]`
+pub enum AttributeLiteral {
+    /// a string literal, stored as a variable length unicode string
+    String(&'static str),
+    /// an integer literal, stored as an `i64`
+    Integer(i64),
+    /// a float literal, stored as an `f64`
+    Float(f64),
+}
+
+/// Describes how a field type is stored. You likely do not want to use this trait; instead use
+/// [`ContainerSchema::schema`]
+pub trait FieldLayout {
+    /// what the type is stored as
+    fn layout() -> Layout;
+}
+
+impl<S, D> FieldLayout for ndarray::ArrayBase<ndarray::OwnedRepr<S>, D>
+where
+    S: H5Type,
+    D: ndarray::Dimension,
+{
+    fn layout() -> Layout {
+        array_layout::<S, D>()
+    }
+}
+
+impl<S, D> FieldLayout for ndarray::ArrayBase<ndarray::ViewRepr<&S>, D>
+where
+    S: H5Type,
+    D: ndarray::Dimension,
+{
+    fn layout() -> Layout {
+        array_layout::<S, D>()
+    }
+}
+
+impl<T> FieldLayout for T
+where
+    T: ContainerSchema,
+{
+    fn layout() -> Layout {
+        Layout {
+            object: ObjectKind::Group,
+            lazy: false,
+            element_type: None,
+            rank: None,
+            nested: Some(T::schema()),
+            attributes: Vec::new(),
+        }
+    }
+}
+
+/// layout of a dataset with elements `S` and dimension `D`
+pub(crate) fn array_layout<S, D>() -> Layout
+where
+    S: H5Type,
+    D: ndarray::Dimension,
+{
+    Layout {
+        object: ObjectKind::Dataset,
+        lazy: false,
+        element_type: Some(S::type_descriptor()),
+        rank: D::NDIM,
+        nested: None,
+        attributes: Vec::new(),
+    }
+}
+
+/// layout of a scalar attribute of type `S`
+pub(crate) fn attribute_layout<S: H5Type>() -> Layout {
+    Layout {
+        object: ObjectKind::Attribute,
+        lazy: false,
+        element_type: Some(S::type_descriptor()),
+        rank: Some(0),
+        nested: None,
+        attributes: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ContainerAttributes, ContainerRead, ContainerSchema, ContainerWrite, Lazy, LazyArray2,
+        WithAttrs,
+    };
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use hdf5::types::{CompoundField, CompoundType, FixedAscii, FloatSize, IntSize};
+    use ndarray::{Array1, Array2, ArrayD, ArrayView1};
+
+    #[derive(ContainerRead, ContainerWrite, ContainerSchema)]
+    #[hdf5(write_complete)]
+    struct Nested {
+        pressure: Array2<f32>,
+    }
+
+    #[derive(ContainerRead, ContainerWrite, ContainerSchema)]
+    #[hdf5(mutate_on_write, select = false)]
+    struct Data {
+        #[hdf5(transpose = "both", rename(write = "vel"), mutate_on_write = false)]
+        velocity: LazyArray2<f64>,
+        dynamic: ArrayD<u8>,
+        nested: Lazy<Nested>,
+        #[hdf5(select = true)]
+        step: u64,
+    }
+
+    #[derive(ContainerWrite, ContainerSchema)]
+    struct View<'a> {
+        grid: ArrayView1<'a, f64>,
+    }
+
+    #[test]
+    fn describes_fields() {
+        let schema = Data::schema();
+
+        assert_eq!(schema.name, "Data");
+        assert!(!schema.write_complete);
+
+        let names: Vec<_> = schema.fields.iter().map(|field| field.rust_name).collect();
+        assert_eq!(names, ["velocity", "dynamic", "nested", "step"]);
+
+        let velocity = schema.field("velocity").unwrap();
+        assert_eq!(
+            (velocity.read_name, velocity.write_name),
+            ("velocity", "vel")
+        );
+        assert!(velocity.transpose_read && velocity.transpose_write);
+        assert!(!velocity.mutate_on_write);
+        assert!(!velocity.select);
+        assert!(velocity.layout.lazy);
+        assert_eq!(velocity.layout.rank, Some(2));
+        assert_eq!(
+            velocity.layout.element_type,
+            Some(TypeDescriptor::Float(FloatSize::U8))
+        );
+
+        let dynamic = schema.field("dynamic").unwrap();
+        assert_eq!(dynamic.layout.rank, None);
+        assert!(dynamic.mutate_on_write);
+
+        let step = schema.field("step").unwrap();
+        assert_eq!(step.layout.object, ObjectKind::Attribute);
+        assert_eq!(step.layout.rank, Some(0));
+        assert!(step.select);
+    }
+
+    #[test]
+    fn nested_schema() {
+        let schema = Data::schema();
+        let nested = schema.field("nested").unwrap();
+
+        assert_eq!(nested.layout.object, ObjectKind::Group);
+        assert!(nested.layout.lazy);
+
+        let nested = nested.layout.nested.as_ref().unwrap();
+        assert_eq!(nested.name, "Nested");
+        assert!(nested.write_complete);
+        assert_eq!(nested.fields[0].layout.object, ObjectKind::Dataset);
+    }
+
+    #[derive(ContainerSchema)]
+    #[allow(dead_code)]
+    /// a "small" container
+    struct Small {
+        /// first line
+        /// second line
+        #[hdf5(
+            rename(read = "g"),
+            units = "m",
+            attrs(long_name = "grid", level = 3, scale = 0.5)
+        )]
+        grid: Array2<u8>,
+        points: Array1<Point>,
+        nested: Empty,
+    }
+
+    #[derive(Clone, Copy)]
+    #[repr(C)]
+    struct Point {
+        x: f64,
+        label: FixedAscii<8>,
+    }
+
+    unsafe impl H5Type for Point {
+        fn type_descriptor() -> TypeDescriptor {
+            TypeDescriptor::Compound(CompoundType {
+                fields: vec![
+                    CompoundField::typed::<f64>("x", 0, 0),
+                    CompoundField::typed::<FixedAscii<8>>("label", 8, 1),
+                ],
+                size: 16,
+            })
+        }
+    }
+
+    #[derive(ContainerWrite, ContainerSchema)]
+    struct Empty {}
+
+    #[test]
+    fn json() {
+        let expected = r#"{
+  "format": "hdf5-derive-schema",
+  "version": 1,
+  "root": {
+    "name": "Small",
+    "docs": "a \"small\" container",
+    "write_complete": false,
+    "fields": [
+      {
+        "rust_name": "grid",
+        "read_name": "g",
+        "write_name": "grid",
+        "docs": "first line\nsecond line",
+        "object": "dataset",
+        "lazy": false,
+        "dtype": {
+          "kind": "uint",
+          "size": 1
+        },
+        "rank": 2,
+        "units": "m",
+        "attributes": [
+          {
+            "name": "long_name",
+            "dtype": {
+              "kind": "string",
+              "size": null,
+              "encoding": "utf-8"
+            },
+            "value": "grid"
+          },
+          {
+            "name": "level",
+            "dtype": {
+              "kind": "int",
+              "size": 8
+            },
+            "value": 3
+          },
+          {
+            "name": "scale",
+            "dtype": {
+              "kind": "float",
+              "size": 8
+            },
+            "value": 0.5
+          }
+        ],
+        "transpose_read": false,
+        "transpose_write": false,
+        "mutate_on_write": false,
+        "select": true,
+        "group": null
+      },
+      {
+        "rust_name": "points",
+        "read_name": "points",
+        "write_name": "points",
+        "docs": "",
+        "object": "dataset",
+        "lazy": false,
+        "dtype": {
+          "kind": "compound",
+          "size": 16,
+          "fields": [
+            {
+              "name": "x",
+              "offset": 0,
+              "dtype": {
+                "kind": "float",
+                "size": 8
+              }
+            },
+            {
+              "name": "label",
+              "offset": 8,
+              "dtype": {
+                "kind": "string",
+                "size": 8,
+                "encoding": "ascii"
+              }
+            }
+          ]
+        },
+        "rank": 1,
+        "units": null,
+        "attributes": [],
+        "transpose_read": false,
+        "transpose_write": false,
+        "mutate_on_write": false,
+        "select": true,
+        "group": null
+      },
+      {
+        "rust_name": "nested",
+        "read_name": "nested",
+        "write_name": "nested",
+        "docs": "",
+        "object": "group",
+        "lazy": false,
+        "dtype": null,
+        "rank": null,
+        "units": null,
+        "attributes": [],
+        "transpose_read": false,
+        "transpose_write": false,
+        "mutate_on_write": false,
+        "select": true,
+        "group": {
+          "name": "Empty",
+          "docs": "",
+          "write_complete": false,
+          "fields": []
+        }
+      }
+    ]
+  }
+}
+"#;
+
+        assert_eq!(Small::schema_json(), expected);
+    }
+
+    #[derive(ContainerAttributes)]
+    struct Grid {
+        #[hdf5(rename(both = "dx"))]
+        spacing: f64,
+        long_name: String,
+    }
+
+    #[derive(ContainerRead, ContainerWrite, ContainerSchema)]
+    struct Annotated {
+        #[hdf5(attrs(level = 3))]
+        grid: WithAttrs<Array2<f64>, Grid>,
+    }
+
+    #[test]
+    fn attributes() {
+        let grid = Annotated::schema().fields[0].layout.clone();
+        assert_eq!(grid.object, ObjectKind::Dataset);
+
+        let names: Vec<_> = grid
+            .attributes
+            .iter()
+            .map(|attribute| attribute.name)
+            .collect();
+        assert_eq!(names, ["dx", "long_name", "level"]);
+
+        let spacing = &grid.attributes[0];
+        assert_eq!(spacing.element_type, TypeDescriptor::Float(FloatSize::U8));
+        assert_eq!(spacing.value, None);
+
+        let level = &grid.attributes[2];
+        assert_eq!(level.element_type, TypeDescriptor::Integer(IntSize::U8));
+        assert_eq!(level.value, Some(AttributeLiteral::Integer(3)));
+    }
+
+    #[test]
+    fn views() {
+        let grid = View::schema().fields[0].layout.clone();
+
+        assert_eq!(grid.object, ObjectKind::Dataset);
+        assert_eq!(grid.rank, Some(1));
+    }
+}
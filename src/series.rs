@@ -0,0 +1,417 @@
+use std::marker::PhantomData;
+
+use hdf5::Dataset;
+use hdf5::Group;
+
+use crate::error;
+use crate::ContainerRead;
+use crate::ContainerWrite;
+use crate::Error;
+
+/// name of the index dataset holding the step number of each snapshot
+const STEPS: &str = "steps";
+/// name of the index dataset holding the simulation time of each snapshot
+const TIMES: &str = "times";
+
+/// The step number and simulation time of a single snapshot in a [`SnapshotSeries`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snapshot {
+    /// the step number the snapshot was written at
+    pub step: u64,
+    /// the simulation time the snapshot was written at
+    pub time: f64,
+}
+
+impl Snapshot {
+    /// name of the group the snapshot is stored in, i.e. `t_000100` for step 100
+    pub fn group_name(&self) -> String {
+        format!("t_{:06}", self.step)
+    }
+}
+
+/// A time-stepped series of containers stored in a single HDF5 group.
+///
+/// Each snapshot is written with [`ContainerWrite::write_hdf5`] to its own group named after the
+/// step number (`t_000100` for step 100). The step numbers and simulation times of all snapshots
+/// are recorded in two one dimensional index datasets, `steps` and `times`, so snapshots can be
+/// listed and looked up without opening every group.
+///
+/// ```
+/// use hdf5_derive::{ContainerRead, ContainerWrite, SnapshotSeries};
+/// use ndarray::Array2;
+///
+/// #[derive(ContainerRead, ContainerWrite)]
+/// struct Flowfield {
+///     velocity: Array2<f64>,
+/// }
+///
+/// let path = "./snapshot_series.h5";
+/// let file = hdf5_derive::File::create(path).unwrap();
+///
+/// let series = SnapshotSeries::<Flowfield>::create(&file).unwrap();
+///
+/// for step in [100, 200, 300] {
+///     let flowfield = Flowfield { velocity: Array2::from_elem((4, 4), step as f64) };
+///     series.write(step, step as f64 * 0.01, &flowfield).unwrap();
+/// }
+///
+/// assert_eq!(series.len(), 3);
+///
+/// let (snapshot, flowfield) = series.read_latest().unwrap().unwrap();
+/// assert_eq!(snapshot.step, 300);
+/// assert_eq!(flowfield.velocity[[0, 0]], 300.);
+///
+/// let (snapshot, _) = series.read_nearest(1.4).unwrap().unwrap();
+/// assert_eq!(snapshot.group_name(), "t_000100");
+///
+/// std::fs::remove_file(path).unwrap();
+/// ```
+pub struct SnapshotSeries<T> {
+    group: Group,
+    steps: Dataset,
+    times: Dataset,
+    _container: PhantomData<T>,
+}
+
+impl<T> SnapshotSeries<T> {
+    /// start a new, empty series in `group`
+    ///
+    /// Errors if `group` already holds a series.
+    pub fn create(group: &Group) -> Result<Self, Error> {
+        let steps = group
+            .new_dataset::<u64>()
+            .shape(0..)
+            .create(STEPS)
+            .map_err(|e| error::CreateDataset::from_field_name(STEPS, e))?;
+
+        let times = group
+            .new_dataset::<f64>()
+            .shape(0..)
+            .create(TIMES)
+            .map_err(|e| error::CreateDataset::from_field_name(TIMES, e))?;
+
+        Ok(Self {
+            group: group.clone(),
+            steps,
+            times,
+            _container: PhantomData,
+        })
+    }
+
+    /// open a series previously started with [`SnapshotSeries::create`]
+    pub fn open(group: &Group) -> Result<Self, Error> {
+        let steps = group
+            .dataset(STEPS)
+            .map_err(|e| error::MissingDataset::from_field_name(STEPS, e))?;
+
+        let times = group
+            .dataset(TIMES)
+            .map_err(|e| error::MissingDataset::from_field_name(TIMES, e))?;
+
+        Ok(Self {
+            group: group.clone(),
+            steps,
+            times,
+            _container: PhantomData,
+        })
+    }
+
+    /// the group the series is stored in
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    /// number of snapshots in the series
+    pub fn len(&self) -> usize {
+        self.steps.size()
+    }
+
+    /// whether or not any snapshots have been written
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// list every snapshot in the series, in the order they were written
+    pub fn snapshots(&self) -> Result<Vec<Snapshot>, Error> {
+        let steps = self
+            .steps
+            .read_raw::<u64>()
+            .map_err(|e| error::SerializeArray::from_field_name(STEPS, e))?;
+
+        let times = self
+            .times
+            .read_raw::<f64>()
+            .map_err(|e| error::SerializeArray::from_field_name(TIMES, e))?;
+
+        let snapshots = steps
+            .into_iter()
+            .zip(times)
+            .map(|(step, time)| Snapshot { step, time })
+            .collect();
+
+        Ok(snapshots)
+    }
+
+    /// find the snapshot written at `step`, if any
+    pub fn find_step(&self, step: u64) -> Result<Option<Snapshot>, Error> {
+        Ok(self.snapshots()?.into_iter().find(|s| s.step == step))
+    }
+
+    /// find the snapshot with the largest step number, if any
+    pub fn latest(&self) -> Result<Option<Snapshot>, Error> {
+        Ok(self.snapshots()?.into_iter().max_by_key(|s| s.step))
+    }
+
+    /// find the snapshot with the simulation time closest to `time`, if any
+    pub fn nearest(&self, time: f64) -> Result<Option<Snapshot>, Error> {
+        let nearest = self
+            .snapshots()?
+            .into_iter()
+            .min_by(|a, b| (a.time - time).abs().total_cmp(&(b.time - time).abs()));
+
+        Ok(nearest)
+    }
+}
+
+impl<T> SnapshotSeries<T>
+where
+    T: ContainerWrite,
+{
+    /// write `container` as a new snapshot at `step` and `time`
+    ///
+    /// The snapshot is only added to the index after the container has been written, so
+    /// a failed write never shows up in [`SnapshotSeries::snapshots`]. If the write fails, the
+    /// group of the snapshot is removed again and both index datasets keep their previous length,
+    /// so the same step can be retried. Errors if a snapshot with the same step already exists.
+    pub fn write(&self, step: u64, time: f64, container: &T) -> Result<Snapshot, Error> {
+        let snapshot = Snapshot { step, time };
+        let name = snapshot.group_name();
+
+        let group = self
+            .group
+            .create_group(&name)
+            .map_err(|e| error::CreateGroup::from_field_name(&name, e))?;
+
+        let index = self.len();
+
+        let written = container
+            .write_hdf5(&group)
+            .and_then(|_| self.append(index, snapshot));
+
+        if let Err(e) = written {
+            // the error of the write is more useful than any error cleaning up after it
+            self.steps.resize(index).ok();
+            self.times.resize(index).ok();
+            self.group.unlink(&name).ok();
+
+            return Err(e);
+        }
+
+        Ok(snapshot)
+    }
+
+    /// grow both index datasets to `index + 1` elements, and record `snapshot` at the end
+    fn append(&self, index: usize, snapshot: Snapshot) -> Result<(), Error> {
+        self.steps
+            .resize(index + 1)
+            .map_err(|e| error::WriteArray::from_field_name(STEPS, e))?;
+
+        self.times
+            .resize(index + 1)
+            .map_err(|e| error::WriteArray::from_field_name(TIMES, e))?;
+
+        self.steps
+            .write_slice(&[snapshot.step], index..index + 1)
+            .map_err(|e| error::WriteSlice::from_field_name(STEPS, e))?;
+
+        self.times
+            .write_slice(&[snapshot.time], index..index + 1)
+            .map_err(|e| error::WriteSlice::from_field_name(TIMES, e))?;
+
+        Ok(())
+    }
+}
+
+impl<T> SnapshotSeries<T>
+where
+    T: ContainerRead,
+{
+    /// read the container stored for `snapshot`
+    pub fn read(&self, snapshot: &Snapshot) -> Result<T, Error> {
+        let name = snapshot.group_name();
+
+        let group = self
+            .group
+            .group(&name)
+            .map_err(|e| error::MissingGroup::from_field_name(&name, e))?;
+
+        T::read_hdf5(&group)
+    }
+
+    /// read the snapshot written at `step`, if any
+    pub fn read_step(&self, step: u64) -> Result<Option<T>, Error> {
+        self.find_step(step)?
+            .map(|snapshot| self.read(&snapshot))
+            .transpose()
+    }
+
+    /// read the snapshot with the largest step number, if any
+    pub fn read_latest(&self) -> Result<Option<(Snapshot, T)>, Error> {
+        self.latest()?
+            .map(|snapshot| Ok((snapshot, self.read(&snapshot)?)))
+            .transpose()
+    }
+
+    /// read the snapshot with the simulation time closest to `time`, if any
+    pub fn read_nearest(&self, time: f64) -> Result<Option<(Snapshot, T)>, Error> {
+        self.nearest(time)?
+            .map(|snapshot| Ok((snapshot, self.read(&snapshot)?)))
+            .transpose()
+    }
+
+    /// iterate over every snapshot in the order they were written. Each container is
+    /// only read when the iterator reaches it
+    pub fn iter(&self) -> Result<impl Iterator<Item = Result<(Snapshot, T), Error>> + '_, Error> {
+        let iter = self
+            .snapshots()?
+            .into_iter()
+            .map(|snapshot| Ok((snapshot, self.read(&snapshot)?)));
+
+        Ok(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::Array1;
+
+    #[derive(ContainerRead, ContainerWrite, Debug, PartialEq, Clone)]
+    struct State {
+        energy: Array1<f64>,
+        step: u64,
+    }
+
+    #[test]
+    fn write_and_reopen() {
+        let path = "./series_write_and_reopen.h5";
+        let file = crate::File::create(path).unwrap();
+
+        let series = SnapshotSeries::<State>::create(&file).unwrap();
+        assert!(series.is_empty());
+        assert!(series.read_latest().unwrap().is_none());
+
+        let first = State {
+            energy: Array1::from_elem(3, 10.),
+            step: 10,
+        };
+        let second = State {
+            energy: Array1::from_elem(3, 30.),
+            step: 30,
+        };
+        let third = State {
+            energy: Array1::from_elem(3, 20.),
+            step: 20,
+        };
+
+        series.write(10, 0.1, &first).unwrap();
+        series.write(30, 0.3, &second).unwrap();
+        series.write(20, 0.2, &third).unwrap();
+
+        // duplicate steps are rejected, and do not modify the index
+        assert!(series.write(20, 0.25, &third).is_err());
+        file.close().unwrap();
+
+        let file = crate::File::open(path).unwrap();
+        let series = SnapshotSeries::<State>::open(&file).unwrap();
+
+        let steps: Vec<u64> = series.snapshots().unwrap().iter().map(|s| s.step).collect();
+        assert_eq!(steps, vec![10, 30, 20]);
+
+        let (snapshot, latest) = series.read_latest().unwrap().unwrap();
+        assert_eq!(
+            snapshot,
+            Snapshot {
+                step: 30,
+                time: 0.3
+            }
+        );
+        assert_eq!(latest, second);
+
+        let (snapshot, _) = series.read_nearest(0.21).unwrap().unwrap();
+        assert_eq!(snapshot.step, 20);
+
+        assert_eq!(series.read_step(10).unwrap(), Some(first.clone()));
+        assert_eq!(series.read_step(40).unwrap(), None);
+
+        let all: Vec<State> = series.iter().unwrap().map(|s| s.unwrap().1).collect();
+        assert_eq!(all, vec![first, second, third]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// writes `state`, then fails if `fail` is set
+    struct Flaky {
+        state: State,
+        fail: bool,
+    }
+
+    impl ContainerWrite for Flaky {
+        fn write_hdf5(&self, container: &Group) -> Result<(), Error> {
+            self.state.write_hdf5(container)?;
+
+            if self.fail {
+                return Err(error::Cancelled::new("energy").into());
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_write_retried() {
+        let file = crate::memory::create_in_memory().unwrap();
+        let series = SnapshotSeries::<Flaky>::create(&file).unwrap();
+
+        let state = State {
+            energy: Array1::from_elem(3, 10.),
+            step: 10,
+        };
+
+        let flaky = Flaky {
+            state: state.clone(),
+            fail: true,
+        };
+        assert!(matches!(
+            series.write(10, 0.1, &flaky),
+            Err(Error::Cancelled(_))
+        ));
+
+        // nothing is left of the failed write
+        assert!(series.is_empty());
+        assert_eq!(series.times.size(), 0);
+        assert!(!file.link_exists("t_000010"));
+
+        let flaky = Flaky {
+            state: state.clone(),
+            fail: false,
+        };
+        series.write(10, 0.1, &flaky).unwrap();
+
+        let series = SnapshotSeries::<State>::open(&file).unwrap();
+        assert_eq!(series.read_step(10).unwrap(), Some(state));
+    }
+
+    #[test]
+    fn open_missing_series() {
+        let path = "./series_open_missing.h5";
+        let file = crate::File::create(path).unwrap();
+
+        assert!(SnapshotSeries::<State>::open(&file).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
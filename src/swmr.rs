@@ -0,0 +1,366 @@
+//! Single-writer / multiple-reader (SWMR) access for monitoring a running simulation
+//!
+//! Requires the `swmr` feature, and HDF5 1.10 or newer.
+//!
+//! A [`SwmrWriter`] creates a file from an initial container, with every array stored in a
+//! dataset that can grow along the leading axis, and then switches the file to SWMR write mode.
+//! After that, [`SwmrWriter::append`] appends the arrays of each new container to the end of
+//! their datasets and flushes them, so that they are visible to readers.
+//!
+//! Other processes open the file with [`SwmrReader`] while it is still being written.
+//! [`SwmrReader::read`] refreshes each dataset before reading it, so every call sees the
+//! data appended so far. [`crate::LazyArray::refresh`] does the same for a single [`crate::LazyArray`].
+//!
+//! Once in SWMR mode, no new groups, datasets or attributes can be created in the file, so
+//! [`SwmrWriter::append`] only appends arrays, with [`ContainerWrite::append_hdf5`]. Scalar
+//! attributes, `#[hdf5(select = false)]` fields and the attributes describing fields are written
+//! once by [`SwmrWriter::create`], and keep the values of the initial container.
+//!
+//! ```
+//! use hdf5_derive::{ContainerRead, ContainerWrite};
+//! use hdf5_derive::swmr::{SwmrReader, SwmrWriter};
+//! use ndarray::{Array1, Array2};
+//!
+//! #[derive(ContainerRead, ContainerWrite)]
+//! struct Probes {
+//!     time: Array1<f64>,
+//!     velocity: Array2<f64>,
+//! }
+//!
+//! let path = "./swmr_doc.h5";
+//!
+//! let initial = Probes { time: Array1::zeros(1), velocity: Array2::zeros((1, 3)) };
+//! let writer = SwmrWriter::create(path, &initial).unwrap();
+//!
+//! // usually in a different process
+//! let reader = SwmrReader::open(path).unwrap();
+//!
+//! let step = Probes { time: Array1::ones(1), velocity: Array2::ones((1, 3)) };
+//! writer.append(&step).unwrap();
+//!
+//! let probes: Probes = reader.read().unwrap();
+//! assert_eq!(probes.time.len(), 2);
+//!
+//! drop(reader);
+//! drop(writer);
+//! std::fs::remove_file(path).unwrap();
+//! ```
+
+use std::cell::Cell;
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use hdf5::Dataset;
+use hdf5::File;
+use hdf5::Group;
+use hdf5::H5Type;
+
+use hdf5_sys::h5d::{H5Dflush, H5Drefresh};
+use hdf5_sys::h5f::{H5Fopen, H5Fstart_swmr_write, H5F_ACC_RDONLY, H5F_ACC_SWMR_READ};
+use hdf5_sys::h5p::H5P_DEFAULT;
+
+use ndarray::ArrayViewD;
+use ndarray::Axis;
+
+use crate::error;
+use crate::ContainerRead;
+use crate::ContainerWrite;
+use crate::Error;
+use crate::Slice;
+
+/// Writes an initial container to a new file, and appends containers to it in SWMR mode
+pub struct SwmrWriter<T> {
+    file: File,
+    _container: PhantomData<T>,
+}
+
+impl<T> SwmrWriter<T>
+where
+    T: ContainerWrite,
+{
+    /// create a file at `path` (truncating it if it exists), write `initial` to it, and switch
+    /// it to SWMR write mode
+    ///
+    /// Every array dataset written by `initial` is created with resizable dimensions, so that
+    /// later containers can be appended to it.
+    pub fn create<P: AsRef<Path>>(path: P, initial: &T) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let path_name = path.display().to_string();
+
+        // SWMR requires the latest file format
+        let file = File::with_options()
+            .with_fapl(|fapl| fapl.libver_latest())
+            .create(path)
+            .map_err(|e| error::OpenFile::from_path(path, e))?;
+
+        {
+            let _scope = ModeScope::enter(Mode::Create);
+            initial.write_hdf5(&file)?;
+        }
+
+        hdf5::sync::sync(|| hdf5::h5check(unsafe { H5Fstart_swmr_write(file.id()) }))
+            .map_err(|e| error::StartSwmr::from_field_name(&path_name, e))?;
+
+        Ok(Self {
+            file,
+            _container: PhantomData,
+        })
+    }
+
+    /// append each array of `container` to the end of its dataset along the leading axis, and
+    /// flush the datasets so that readers can see the new data
+    ///
+    /// Arrays are appended independently, so they do not need to have the same length along
+    /// the leading axis. The remaining dimensions must match the dataset. Scalar attributes and
+    /// `#[hdf5(select = false)]` fields of `container` are not written.
+    pub fn append(&self, container: &T) -> Result<(), Error> {
+        container.append_hdf5(&self.file)
+    }
+
+    /// the underlying file
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+}
+
+/// Reads containers from a file that is being written by a [`SwmrWriter`]
+pub struct SwmrReader {
+    file: File,
+}
+
+impl SwmrReader {
+    /// open the file at `path` for reading in SWMR mode
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        let file = open_swmr_read(path).map_err(|e| error::OpenFile::from_path(path, e))?;
+
+        Ok(Self { file })
+    }
+
+    /// read a container from the root group of the file, refreshing each dataset first so
+    /// that all data appended so far is read
+    pub fn read<T: ContainerRead>(&self) -> Result<T, Error> {
+        let _scope = ModeScope::enter(Mode::Refresh);
+        T::read_hdf5(&self.file)
+    }
+
+    /// the underlying file
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+}
+
+fn open_swmr_read(path: &Path) -> hdf5::Result<File> {
+    let path = path.to_str().ok_or("file path is not valid UTF-8")?;
+    let path = CString::new(path).map_err(|_| "file path contains a null byte")?;
+
+    hdf5::sync::sync(|| unsafe {
+        let id = hdf5::h5check(H5Fopen(
+            path.as_ptr(),
+            H5F_ACC_RDONLY | H5F_ACC_SWMR_READ,
+            H5P_DEFAULT,
+        ))?;
+        hdf5::from_id::<File>(id)
+    })
+}
+
+/// refresh the metadata of `dataset`, so that data appended by a SWMR writer (and the new
+/// extents of the dataset) become visible
+pub(crate) fn refresh(dataset: &Dataset, array_name: &str) -> Result<(), Error> {
+    hdf5::sync::sync(|| hdf5::h5check(unsafe { H5Drefresh(dataset.id()) }))
+        .map_err(|e| error::RefreshDataset::from_field_name(array_name, e))?;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// the SWMR operation in progress on this thread
+pub(crate) enum Mode {
+    /// array datasets are created with resizable dimensions
+    Create,
+    /// array datasets are refreshed before they are read
+    Refresh,
+}
+
+thread_local! {
+    static MODE: Cell<Option<Mode>> = const { Cell::new(None) };
+}
+
+/// the SWMR operation in progress on this thread, if any
+pub(crate) fn mode() -> Option<Mode> {
+    MODE.with(|m| m.get())
+}
+
+/// marks a SWMR operation as in progress until dropped
+struct ModeScope {
+    previous: Option<Mode>,
+}
+
+impl ModeScope {
+    fn enter(mode: Mode) -> Self {
+        let previous = MODE.with(|m| m.replace(Some(mode)));
+        Self { previous }
+    }
+}
+
+impl Drop for ModeScope {
+    fn drop(&mut self) {
+        MODE.with(|m| m.set(self.previous));
+    }
+}
+
+/// grow the dataset `array_name` along `selected_axis`, write `array` to the new region, and
+/// flush the dataset
+pub(crate) fn append_array<T: H5Type + Clone>(
+    group: &Group,
+    array_name: &str,
+    array: ArrayViewD<'_, T>,
+    selected_axis: usize,
+) -> Result<(), Error> {
+    let dataset = group
+        .dataset(array_name)
+        .map_err(|e| error::FetchDataset::from_field_name(array_name, e))?;
+
+    let mut shape = dataset.shape();
+    let len = if selected_axis < array.ndim() {
+        array.len_of(Axis(selected_axis))
+    } else {
+        0
+    };
+
+    let offset = match shape.get_mut(selected_axis) {
+        Some(current) => {
+            let offset = *current;
+            *current += len;
+            offset
+        }
+        None => 0,
+    };
+
+    dataset
+        .resize(shape)
+        .map_err(|e| error::WriteSlice::from_field_name(array_name, e))?;
+
+    // hdf5 can only write slices from standard layout arrays
+    let array = array.as_standard_layout();
+    let info = crate::axis_selection(
+        array.ndim(),
+        selected_axis,
+        Slice::from(offset..offset + len),
+    );
+
+    dataset
+        .write_slice(array.view(), info)
+        .map_err(|e| error::WriteSlice::from_field_name(array_name, e))?;
+
+    hdf5::sync::sync(|| hdf5::h5check(unsafe { H5Dflush(dataset.id()) }))
+        .map_err(|e| error::FlushDataset::from_field_name(array_name, e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::{Array1, Array2};
+
+    #[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+    struct Nested {
+        energy: Array1<f64>,
+    }
+
+    #[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+    struct Series {
+        #[hdf5(transpose = "both")]
+        velocity: Array2<f64>,
+        nested: Nested,
+    }
+
+    #[test]
+    fn reader_sees_appends() {
+        let path = "./swmr_reader_sees_appends.h5";
+
+        let initial = Series {
+            velocity: Array2::zeros((1, 2)),
+            nested: Nested {
+                energy: Array1::zeros(1),
+            },
+        };
+        let writer = SwmrWriter::create(path, &initial).unwrap();
+        let reader = SwmrReader::open(path).unwrap();
+
+        let dataset = reader.file().dataset("nested/energy").unwrap();
+        let mut lazy = crate::LazyArray1::<f64>::new(dataset).unwrap();
+        assert_eq!(lazy.shape(), vec![1]);
+
+        let first = Series {
+            velocity: Array2::ones((2, 2)),
+            nested: Nested {
+                energy: Array1::ones(2),
+            },
+        };
+        writer.append(&first).unwrap();
+
+        let second = Series {
+            velocity: Array2::from_elem((1, 2), 2.),
+            nested: Nested {
+                energy: Array1::from_elem(1, 2.),
+            },
+        };
+        writer.append(&second).unwrap();
+
+        let read: Series = reader.read().unwrap();
+        assert_eq!(read.velocity.shape(), &[4, 2]);
+        assert_eq!(read.nested.energy.to_vec(), vec![0., 1., 1., 2.]);
+        assert_eq!(read.velocity.column(1).to_vec(), vec![0., 1., 1., 2.]);
+
+        lazy.refresh().unwrap();
+        assert_eq!(lazy.shape(), vec![4]);
+
+        drop(lazy);
+        drop(reader);
+        drop(writer);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn append_leaves_attributes() {
+        #[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+        struct Run {
+            time: Array1<f64>,
+            step: u64,
+            #[hdf5(select = false)]
+            grid: Array1<f64>,
+        }
+
+        let path = "./swmr_append_leaves_attributes.h5";
+
+        let initial = Run {
+            time: Array1::zeros(1),
+            step: 0,
+            grid: Array1::from_vec(vec![0., 1.]),
+        };
+        let writer = SwmrWriter::create(path, &initial).unwrap();
+
+        // scalars and unselected fields cannot be written in SWMR mode, they keep their initial values
+        let next = Run {
+            time: Array1::ones(2),
+            step: 5,
+            grid: Array1::zeros(3),
+        };
+        writer.append(&next).unwrap();
+        drop(writer);
+
+        let read = Run::read_hdf5_from_path(path).unwrap();
+        assert_eq!(read.time.to_vec(), vec![0., 1., 1.]);
+        assert_eq!(read.step, 0);
+        assert_eq!(read.grid, initial.grid);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,581 @@
+//! Checking that a file has the layout a container expects, without reading any data
+//!
+//! [`crate::ContainerRead::validate_hdf5`] walks every field of a derived container, and checks that the
+//! dataset, attribute or group backing it exists, is the right kind of object, and has a rank and datatype
+//! that can be read into the Rust type. Only metadata is read, so validating a large file is cheap, and
+//! every problem is collected instead of stopping at the first one:
+//!
+//! ```
+//! use hdf5_derive::{ContainerRead, ContainerWrite};
+//! use hdf5_derive::error::ErrorKind;
+//! use ndarray::{Array2, Array3};
+//!
+//! #[derive(ContainerWrite)]
+//! struct Written {
+//!     velocity: Array2<f64>,
+//!     step: u64,
+//! }
+//!
+//! #[derive(ContainerRead)]
+//! struct Expected {
+//!     velocity: Array3<f64>,
+//!     pressure: Array3<f64>,
+//!     step: u64,
+//! }
+//!
+//! let file = hdf5_derive::memory::create_in_memory().unwrap();
+//! Written { velocity: Array2::zeros((4, 4)), step: 10 }.write_hdf5(&file).unwrap();
+//!
+//! let report = Expected::validate_hdf5(&file);
+//! assert!(!report.is_valid());
+//!
+//! let kinds: Vec<ErrorKind> = report.problems().iter().map(|problem| problem.kind()).collect();
+//! assert_eq!(kinds, [ErrorKind::DimensionMismatch, ErrorKind::NotFound]);
+//! assert_eq!(report.problems()[1].path(), Some("/pressure"));
+//!
+//! // `step` was fine
+//! assert_eq!(report.checked()[0].field_path, "step");
+//!
+//! // one line per problem
+//! println!("{report}");
+//! ```
+
+use std::fmt;
+
+use hdf5::Group;
+
+use crate::error;
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The kind of HDF5 object backing a field
+pub enum ObjectKind {
+    /// arrays, and [`crate::LazyArray`]
+    Dataset,
+    /// scalars
+    Attribute,
+    /// nested containers
+    Group,
+}
+
+impl fmt::Display for ObjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ObjectKind::Dataset => "dataset",
+            ObjectKind::Attribute => "attribute",
+            ObjectKind::Group => "group",
+        };
+
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A dataset, attribute or group that can be read into the Rust type of its field
+pub struct CheckedObject {
+    /// full HDF5 path of the object
+    pub path: String,
+    /// chain of Rust field names leading to the object, separated by `.`
+    pub field_path: String,
+    /// the kind of object
+    pub kind: ObjectKind,
+    /// shape of the dataset or attribute in the file. Empty for groups and scalar attributes
+    pub shape: Vec<usize>,
+    /// whether or not the field is transposed when read, in which case the Rust value has the reverse
+    /// of [`CheckedObject::shape`]
+    pub transposed: bool,
+}
+
+#[derive(Debug, Clone)]
+/// A field whose type does not implement validation, so it may or may not be readable
+pub struct UncheckedObject {
+    /// full HDF5 path of the object
+    pub path: String,
+    /// chain of Rust field names leading to the object, separated by `.`
+    pub field_path: String,
+    /// name of the Rust type that could not be checked
+    pub type_name: &'static str,
+}
+
+#[derive(Debug, Default)]
+/// Every problem found by [`crate::ContainerRead::validate_hdf5`], along with the objects that were fine
+/// and those that could not be checked
+pub struct ValidationReport {
+    checked: Vec<CheckedObject>,
+    unchecked: Vec<UncheckedObject>,
+    problems: Vec<Error>,
+}
+
+impl ValidationReport {
+    /// whether or not reading the container would get past every field that could be checked. See
+    /// [`ValidationReport::unchecked`] for the fields that could not
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// whether or not every field was checked, see [`ValidationReport::unchecked`]
+    pub fn is_complete(&self) -> bool {
+        self.unchecked.is_empty()
+    }
+
+    /// the problems found, in field order. Each has its [`Error::path`] and [`Error::field_path`] set
+    pub fn problems(&self) -> &[Error] {
+        &self.problems
+    }
+
+    /// take the problems found out of the report
+    pub fn into_problems(self) -> Vec<Error> {
+        self.problems
+    }
+
+    /// the objects that passed every check, in field order
+    pub fn checked(&self) -> &[CheckedObject] {
+        &self.checked
+    }
+
+    /// the fields whose types do not implement validation, such as hand-written containers that do not
+    /// override [`crate::ContainerRead::validate_hdf5`]
+    pub fn unchecked(&self) -> &[UncheckedObject] {
+        &self.unchecked
+    }
+
+    /// record a problem with the field being validated. Used when implementing
+    /// [`crate::ReadGroup::validate_group`]
+    pub fn push_problem<E: Into<Error>>(&mut self, problem: E) {
+        self.problems.push(problem.into());
+    }
+
+    /// record that the object `name` in `group` passed every check. Used when implementing
+    /// [`crate::ReadGroup::validate_group`]
+    pub fn push_checked(
+        &mut self,
+        group: &Group,
+        name: &str,
+        kind: ObjectKind,
+        shape: &[usize],
+        transposed: bool,
+    ) {
+        self.checked.push(CheckedObject {
+            path: object_path(group, name),
+            field_path: String::new(),
+            kind,
+            shape: shape.to_vec(),
+            transposed,
+        });
+    }
+
+    /// record that the object at `path`, read as `type_name`, could not be checked. Used by the default
+    /// implementations of [`crate::ReadGroup::validate_group`] and [`crate::ContainerRead::validate_hdf5`]
+    pub fn push_unchecked(&mut self, path: String, type_name: &'static str) {
+        self.unchecked.push(UncheckedObject {
+            path,
+            field_path: String::new(),
+            type_name,
+        });
+    }
+
+    /// record the errors in `results`, returning whether or not there were none
+    pub(crate) fn push_results<I>(&mut self, results: I) -> bool
+    where
+        I: IntoIterator<Item = Result<(), Error>>,
+    {
+        let problems_before = self.problems.len();
+        self.problems
+            .extend(results.into_iter().filter_map(Result::err));
+
+        self.problems.len() == problems_before
+    }
+
+    /// move everything found in a nested container into this report
+    pub(crate) fn append(&mut self, mut other: ValidationReport) {
+        self.checked.append(&mut other.checked);
+        self.unchecked.append(&mut other.unchecked);
+        self.problems.append(&mut other.problems);
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_valid() {
+            write!(
+                f,
+                "checked {} objects, no problems found",
+                self.checked.len()
+            )?;
+        } else {
+            write!(f, "found {} problems:", self.problems.len())?;
+        }
+
+        for problem in &self.problems {
+            // the first line of the message, without the location and source paragraphs
+            let message = problem.to_string();
+            let message = message.lines().next().unwrap_or_default();
+
+            match problem.path() {
+                Some(path) => write!(f, "\n  `{path}`: {message}")?,
+                None => write!(f, "\n  {message}")?,
+            }
+        }
+
+        if !self.is_complete() {
+            write!(
+                f,
+                "\n{} objects could not be checked:",
+                self.unchecked.len()
+            )?;
+
+            for unchecked in &self.unchecked {
+                write!(
+                    f,
+                    "\n  `{}`: `{}` does not implement validation",
+                    unchecked.path, unchecked.type_name
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// validate a single field of a derived container, adding the field to the context of every problem
+/// and checked object found by `f`
+#[doc(hidden)]
+pub fn field<F>(report: &mut ValidationReport, group: &Group, name: &str, rust_field: &str, f: F)
+where
+    F: FnOnce(&mut ValidationReport),
+{
+    let problems_before = report.problems.len();
+    let checked_before = report.checked.len();
+    let unchecked_before = report.unchecked.len();
+
+    f(report);
+
+    for problem in &mut report.problems[problems_before..] {
+        problem.context_mut().push_field(group, name, rust_field);
+    }
+
+    for checked in &mut report.checked[checked_before..] {
+        checked.field_path = if checked.field_path.is_empty() {
+            rust_field.to_string()
+        } else {
+            format!("{rust_field}.{}", checked.field_path)
+        };
+    }
+
+    for unchecked in &mut report.unchecked[unchecked_before..] {
+        unchecked.field_path = if unchecked.field_path.is_empty() {
+            rust_field.to_string()
+        } else {
+            format!("{rust_field}.{}", unchecked.field_path)
+        };
+    }
+}
+
+/// full HDF5 path of the object `name` in `group`
+pub(crate) fn object_path(group: &Group, name: &str) -> String {
+    let group_path = group.name();
+
+    if group_path.ends_with('/') {
+        format!("{group_path}{name}")
+    } else {
+        format!("{group_path}/{name}")
+    }
+}
+
+/// the kind of object stored as `name` in `group`, if there is one
+fn object_kind(group: &Group, name: &str) -> Option<ObjectKind> {
+    if group.link_exists(name) {
+        if group.group(name).is_ok() {
+            Some(ObjectKind::Group)
+        } else if group.dataset(name).is_ok() {
+            Some(ObjectKind::Dataset)
+        } else {
+            None
+        }
+    } else if group
+        .attr_names()
+        .is_ok_and(|names| names.iter().any(|n| n == name))
+    {
+        Some(ObjectKind::Attribute)
+    } else {
+        None
+    }
+}
+
+/// the result of opening an object of kind `expected`, recording a problem if it failed because
+/// the object is missing or of a different kind
+fn open<T>(
+    group: &Group,
+    name: &str,
+    expected: ObjectKind,
+    report: &mut ValidationReport,
+    opened: Result<T, hdf5::Error>,
+    missing: impl FnOnce(hdf5::Error) -> Error,
+) -> Option<T> {
+    match opened {
+        Ok(object) => Some(object),
+        Err(e) => {
+            match object_kind(group, name) {
+                Some(actual) if actual != expected => {
+                    report.push_problem(error::WrongObjectKind::new(name, expected, actual))
+                }
+                _ => report.push_problem(missing(e)),
+            }
+
+            None
+        }
+    }
+}
+
+/// open the dataset `name` of `group`, or record why it could not be opened
+pub(crate) fn dataset(
+    group: &Group,
+    name: &str,
+    report: &mut ValidationReport,
+) -> Option<hdf5::Dataset> {
+    open(
+        group,
+        name,
+        ObjectKind::Dataset,
+        report,
+        group.dataset(name),
+        |e| error::MissingDataset::from_field_name(name, e).into(),
+    )
+}
+
+/// open the attribute `name` of `group`, or record why it could not be opened
+pub(crate) fn attribute(
+    group: &Group,
+    name: &str,
+    report: &mut ValidationReport,
+) -> Option<hdf5::Attribute> {
+    open(
+        group,
+        name,
+        ObjectKind::Attribute,
+        report,
+        group.attr(name),
+        |e| error::MissingAttribute::from_field_name(name, e).into(),
+    )
+}
+
+/// open the group `name` of `group`, or record why it could not be opened
+pub(crate) fn group(group: &Group, name: &str, report: &mut ValidationReport) -> Option<Group> {
+    open(
+        group,
+        name,
+        ObjectKind::Group,
+        report,
+        group.group(name),
+        |e| error::MissingGroup::from_field_name(name, e).into(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::{ContainerRead, ContainerWrite, LazyArray2};
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::{Array1, Array2};
+
+    #[derive(ContainerRead, ContainerWrite)]
+    struct Nested {
+        pressure: Array2<f32>,
+    }
+
+    #[derive(ContainerRead, ContainerWrite)]
+    struct Data {
+        #[hdf5(transpose = "read")]
+        velocity: Array2<f64>,
+        nested: Nested,
+        step: u64,
+    }
+
+    #[derive(ContainerRead, ContainerWrite)]
+    struct LazyData {
+        velocity: LazyArray2<f32>,
+    }
+
+    #[test]
+    fn valid_file() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        let data = Data {
+            velocity: Array2::zeros((3, 2)),
+            nested: Nested {
+                pressure: Array2::zeros((2, 2)),
+            },
+            step: 4,
+        };
+        data.write_hdf5(&file).unwrap();
+
+        let report = Data::validate_hdf5(&file);
+        assert!(report.is_valid(), "{report}");
+
+        let paths: Vec<(&str, &str, ObjectKind)> = report
+            .checked()
+            .iter()
+            .map(|checked| {
+                (
+                    checked.path.as_str(),
+                    checked.field_path.as_str(),
+                    checked.kind,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            paths,
+            [
+                ("/velocity", "velocity", ObjectKind::Dataset),
+                ("/nested", "nested", ObjectKind::Group),
+                ("/nested/pressure", "nested.pressure", ObjectKind::Dataset),
+                ("/step", "step", ObjectKind::Attribute),
+            ]
+        );
+
+        let velocity = &report.checked()[0];
+        assert_eq!(velocity.shape, [3, 2]);
+        assert!(velocity.transposed);
+    }
+
+    #[test]
+    fn collects_every_problem() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        // wrong datatype and rank
+        file.new_dataset::<u8>()
+            .shape(4)
+            .create("velocity")
+            .unwrap();
+        // a dataset where the group should be
+        file.new_dataset::<f32>()
+            .shape((2, 2))
+            .create("nested")
+            .unwrap();
+        // `step` is missing
+
+        let report = Data::validate_hdf5(&file);
+
+        let problems: Vec<(ErrorKind, Option<&str>)> = report
+            .problems()
+            .iter()
+            .map(|problem| (problem.kind(), problem.path()))
+            .collect();
+
+        assert_eq!(
+            problems,
+            [
+                (ErrorKind::DimensionMismatch, Some("/velocity")),
+                (ErrorKind::DatatypeMismatch, Some("/velocity")),
+                (ErrorKind::WrongObjectKind, Some("/nested")),
+                (ErrorKind::NotFound, Some("/step")),
+            ]
+        );
+
+        assert!(report.checked().is_empty());
+        assert_eq!(report.to_string().lines().count(), 5);
+    }
+
+    #[test]
+    fn nested_problems() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        let data = Data {
+            velocity: Array2::zeros((3, 2)),
+            nested: Nested {
+                pressure: Array2::zeros((2, 2)),
+            },
+            step: 4,
+        };
+        data.write_hdf5(&file).unwrap();
+
+        file.group("nested").unwrap().unlink("pressure").unwrap();
+
+        let report = Data::validate_hdf5(&file);
+        assert_eq!(report.problems().len(), 1);
+
+        let problem = &report.problems()[0];
+        assert_eq!(problem.path(), Some("/nested/pressure"));
+        assert_eq!(problem.field_path().as_deref(), Some("nested.pressure"));
+    }
+
+    #[test]
+    fn lazy_array_requires_exact_datatype() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        // `f64` can be converted to `f32`, but not memory mapped as one
+        file.new_dataset::<f64>()
+            .shape((2, 2))
+            .create("velocity")
+            .unwrap();
+
+        let report = LazyData::validate_hdf5(&file);
+        assert_eq!(report.problems().len(), 1);
+        assert_eq!(report.problems()[0].kind(), ErrorKind::DatatypeMismatch);
+
+        // an eager array accepts it
+        #[derive(ContainerRead, ContainerWrite)]
+        struct Eager {
+            velocity: Array2<f32>,
+        }
+
+        assert!(Eager::validate_hdf5(&file).is_valid());
+    }
+
+    #[test]
+    fn attribute_rank() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        #[derive(ContainerRead, ContainerWrite)]
+        struct Scalar {
+            step: u64,
+        }
+
+        file.new_attr::<u64>()
+            .shape(3)
+            .create("step")
+            .unwrap()
+            .write(&Array1::<u64>::zeros(3))
+            .unwrap();
+
+        let report = Scalar::validate_hdf5(&file);
+        assert_eq!(report.problems().len(), 1);
+        assert_eq!(report.problems()[0].kind(), ErrorKind::DimensionMismatch);
+    }
+
+    /// written by hand, without `validate_hdf5`
+    struct Manual;
+
+    impl ContainerRead for Manual {
+        fn read_hdf5(_container: &Group) -> Result<Self, Error> {
+            Ok(Manual)
+        }
+    }
+
+    #[test]
+    fn unchecked_fields() {
+        let file = crate::memory::create_in_memory().unwrap();
+        file.create_group("manual").unwrap();
+
+        #[derive(ContainerRead)]
+        struct Outer {
+            manual: Manual,
+        }
+
+        let report = Outer::validate_hdf5(&file);
+        assert!(report.is_valid());
+        assert!(!report.is_complete());
+
+        let unchecked = &report.unchecked()[0];
+        assert_eq!(unchecked.path, "/manual");
+        assert_eq!(unchecked.field_path, "manual");
+        assert!(unchecked.type_name.ends_with("Manual"));
+
+        // the unchecked field is still readable
+        let Outer { manual: Manual } = Outer::read_hdf5(&file).unwrap();
+    }
+}
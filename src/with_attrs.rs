@@ -0,0 +1,377 @@
+use hdf5::types::{TypeDescriptor, VarLenUnicode};
+use hdf5::Group;
+use hdf5::H5Type;
+use hdf5::Location;
+use ndarray::Slice;
+
+use crate::metadata;
+use crate::metadata::StoredAsObject;
+use crate::schema;
+use crate::validate::ValidationReport;
+use crate::Error;
+use crate::ReadGroup;
+use crate::WriteGroup;
+
+/// Metadata stored as attributes on a dataset or group, instead of as objects in a group.
+/// Derived with [`macro@crate::ContainerAttributes`], and used with [`WithAttrs`].
+///
+/// Every field must implement [`AttributeValue`], which includes numbers and `String`s. `rename`
+/// works the same as for [`crate::ContainerWrite`]; other attributes are ignored. Writing replaces
+/// existing attributes of the same name.
+pub trait ContainerAttributes {
+    /// write each field to an attribute of `location`
+    fn write_attributes(&self, location: &Location) -> Result<(), Error>;
+
+    /// read each field from an attribute of `location`
+    fn read_attributes(location: &Location) -> Result<Self, Error>
+    where
+        Self: Sized;
+
+    /// describe the attributes written by [`ContainerAttributes::write_attributes`]
+    fn attributes() -> Vec<schema::Attribute>
+    where
+        Self: Sized;
+}
+
+/// A value stored in a single scalar attribute. You likely do not want to use this trait;
+/// instead use the fields of a [`ContainerAttributes`] struct
+pub trait AttributeValue {
+    /// write the value to the attribute `name` of `location`, replacing its existing value
+    fn write_attribute(&self, location: &Location, name: &str) -> Result<(), Error>;
+
+    /// read the value from the attribute `name` of `location`
+    fn read_attribute(location: &Location, name: &str) -> Result<Self, Error>
+    where
+        Self: Sized;
+
+    /// element type of the attribute
+    fn type_descriptor() -> TypeDescriptor
+    where
+        Self: Sized;
+}
+
+macro_rules! attribute_values {
+    ($($scalar_type:ty),+) => {
+        $(
+            impl AttributeValue for $scalar_type {
+                fn write_attribute(&self, location: &Location, name: &str) -> Result<(), Error> {
+                    metadata::write_scalar_attribute(location, name, self)
+                }
+
+                fn read_attribute(location: &Location, name: &str) -> Result<Self, Error> {
+                    metadata::scalar_attribute(location, name)
+                }
+
+                fn type_descriptor() -> TypeDescriptor {
+                    <$scalar_type as H5Type>::type_descriptor()
+                }
+            }
+        )+
+    }
+}
+
+attribute_values!(
+    f32,
+    f64,
+    i16,
+    i32,
+    i64,
+    i8,
+    isize,
+    u16,
+    u8,
+    u32,
+    u64,
+    usize,
+    VarLenUnicode
+);
+
+impl AttributeValue for String {
+    fn write_attribute(&self, location: &Location, name: &str) -> Result<(), Error> {
+        metadata::write_string_attribute(location, name, self)
+    }
+
+    fn read_attribute(location: &Location, name: &str) -> Result<Self, Error> {
+        metadata::string_attribute(location, name)
+    }
+
+    fn type_descriptor() -> TypeDescriptor {
+        <VarLenUnicode as H5Type>::type_descriptor()
+    }
+}
+
+/// A field stored as `A`, with the metadata `M` written as attributes on the dataset or group
+/// backing it rather than on the parent group.
+///
+/// ```
+/// use hdf5_derive::{ContainerAttributes, ContainerRead, ContainerWrite, WithAttrs};
+/// use hdf5_derive::metadata::string_attribute;
+/// use ndarray::Array2;
+///
+/// #[derive(ContainerAttributes)]
+/// struct Grid {
+///     long_name: String,
+///     #[hdf5(rename(both = "dx"))]
+///     spacing: f64,
+/// }
+///
+/// #[derive(ContainerRead, ContainerWrite)]
+/// struct Snapshot {
+///     velocity: WithAttrs<Array2<f64>, Grid>,
+/// }
+///
+/// let file = hdf5_derive::memory::create_in_memory().unwrap();
+///
+/// let grid = Grid { long_name: "streamwise velocity".into(), spacing: 0.1 };
+/// let snapshot = Snapshot { velocity: WithAttrs::new(Array2::zeros((4, 4)), grid) };
+/// snapshot.write_hdf5(&file).unwrap();
+///
+/// let velocity = file.dataset("velocity").unwrap();
+/// assert_eq!(string_attribute(&velocity, "long_name").unwrap(), "streamwise velocity");
+///
+/// let read = Snapshot::read_hdf5(&file).unwrap();
+/// assert_eq!(read.velocity.attrs.spacing, 0.1);
+/// ```
+///
+/// `A` must be stored as a dataset or group (see [`crate::metadata::StoredAsObject`]), so wrapping a scalar
+/// does not compile. The macro attributes of the field apply to `A`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithAttrs<A, M> {
+    /// the data of the field
+    pub data: A,
+    /// the metadata written as attributes of the data
+    pub attrs: M,
+}
+
+impl<A, M> WithAttrs<A, M> {
+    /// wrap `data` with the metadata `attrs`
+    pub fn new(data: A, attrs: M) -> Self {
+        Self { data, attrs }
+    }
+
+    /// the data, without the metadata
+    pub fn into_inner(self) -> A {
+        self.data
+    }
+}
+
+impl<A, M> ReadGroup for WithAttrs<A, M>
+where
+    A: ReadGroup,
+    M: ContainerAttributes,
+{
+    fn read_group(group: &Group, array_name: &str, transpose: bool) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let data = A::read_group(group, array_name, transpose)?;
+        Ok(Self::new(data, read_metadata(group, array_name)?))
+    }
+
+    fn read_group_selection(
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        selection: Slice,
+    ) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let data = A::read_group_selection(group, array_name, transpose, selection)?;
+        Ok(Self::new(data, read_metadata(group, array_name)?))
+    }
+
+    fn read_group_report(group: &Group, array_name: &str, transpose: bool) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let data = A::read_group_report(group, array_name, transpose)?;
+        Ok(Self::new(data, read_metadata(group, array_name)?))
+    }
+
+    /// checks `A`, and that the metadata can be read once `A` exists
+    fn validate_group(
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        report: &mut ValidationReport,
+    ) where
+        Self: Sized,
+    {
+        A::validate_group(group, array_name, transpose, report);
+
+        if metadata::object(group, array_name).is_some() {
+            if let Err(e) = read_metadata::<M>(group, array_name) {
+                report.push_problem(e);
+            }
+        }
+    }
+}
+
+/// read `M` from the attributes of the dataset or group `name` of `group`
+fn read_metadata<M: ContainerAttributes>(group: &Group, name: &str) -> Result<M, Error> {
+    match metadata::object(group, name) {
+        Some(location) => M::read_attributes(&location),
+        None => Err(crate::error::AttributesUnsupported::new(name).into()),
+    }
+}
+
+impl<A: StoredAsObject, M> StoredAsObject for WithAttrs<A, M> {}
+
+impl<A, M> WriteGroup for WithAttrs<A, M>
+where
+    A: WriteGroup + StoredAsObject,
+    M: ContainerAttributes,
+{
+    fn write_group(
+        &self,
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        mutate_on_write: bool,
+    ) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        self.data
+            .write_group(group, array_name, transpose, mutate_on_write)?;
+        metadata::attach::<A, _>(group, array_name, |location| {
+            self.attrs.write_attributes(location)
+        })
+    }
+
+    fn write_group_selection(
+        &self,
+        group: &Group,
+        array_name: &str,
+        transpose: bool,
+        mutate_on_write: bool,
+        offset: usize,
+    ) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        self.data
+            .write_group_selection(group, array_name, transpose, mutate_on_write, offset)?;
+        metadata::attach::<A, _>(group, array_name, |location| {
+            self.attrs.write_attributes(location)
+        })
+    }
+
+    /// the attributes are left as they were first written
+    fn append_group(&self, group: &Group, array_name: &str, transpose: bool) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        self.data.append_group(group, array_name, transpose)
+    }
+}
+
+impl<A, M> schema::FieldLayout for WithAttrs<A, M>
+where
+    A: schema::FieldLayout,
+    M: ContainerAttributes,
+{
+    fn layout() -> schema::Layout {
+        let mut layout = A::layout();
+        layout.attributes.extend(M::attributes());
+        layout
+    }
+}
+
+impl<A, M> crate::LazyTwin for WithAttrs<A, M>
+where
+    A: crate::LazyTwin,
+    M: ContainerAttributes,
+{
+    type Lazy = WithAttrs<A::Lazy, M>;
+
+    fn from_lazy(lazy: Self::Lazy, transpose: bool) -> Result<Self, Error> {
+        Ok(Self::new(A::from_lazy(lazy.data, transpose)?, lazy.attrs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::{scalar_attribute, string_attribute};
+    use crate::{ContainerAttributes, ContainerRead, ContainerWrite};
+    // required for proc macros to work
+    use crate as hdf5_derive;
+
+    use ndarray::Array2;
+
+    #[derive(ContainerAttributes, Debug, PartialEq, Clone)]
+    struct Grid {
+        long_name: String,
+        #[hdf5(rename(write = "nx", read = "nx"))]
+        points: u32,
+    }
+
+    #[derive(ContainerRead, ContainerWrite)]
+    struct Nested {
+        pressure: Array2<f32>,
+    }
+
+    #[derive(ContainerRead, ContainerWrite)]
+    struct Data {
+        #[hdf5(attrs(
+            long_name = "streamwise velocity",
+            grid = "staggered",
+            level = 3,
+            scale = 0.5
+        ))]
+        velocity: Array2<f64>,
+        #[hdf5(transpose = "both")]
+        energy: WithAttrs<Array2<f64>, Grid>,
+        nested: WithAttrs<Nested, Grid>,
+    }
+
+    #[test]
+    fn writes_attributes() {
+        let file = crate::memory::create_in_memory().unwrap();
+
+        let grid = Grid {
+            long_name: "energy".into(),
+            points: 3,
+        };
+
+        let data = Data {
+            velocity: Array2::zeros((2, 3)),
+            energy: WithAttrs::new(
+                Array2::from_shape_fn((2, 3), |(i, j)| (i + j) as f64),
+                grid.clone(),
+            ),
+            nested: WithAttrs::new(
+                Nested {
+                    pressure: Array2::zeros((2, 2)),
+                },
+                grid.clone(),
+            ),
+        };
+        data.write_hdf5(&file).unwrap();
+
+        let velocity = file.dataset("velocity").unwrap();
+        assert_eq!(
+            string_attribute(&velocity, "long_name").unwrap(),
+            "streamwise velocity"
+        );
+        assert_eq!(string_attribute(&velocity, "grid").unwrap(), "staggered");
+        assert_eq!(scalar_attribute::<i64>(&velocity, "level").unwrap(), 3);
+        assert_eq!(scalar_attribute::<f64>(&velocity, "scale").unwrap(), 0.5);
+
+        // the metadata is on the dataset, not the parent group
+        let energy = file.dataset("energy").unwrap();
+        assert_eq!(scalar_attribute::<u32>(&energy, "nx").unwrap(), 3);
+        assert!(string_attribute(&file, "long_name").is_err());
+
+        let nested = file.group("nested").unwrap();
+        assert_eq!(string_attribute(&nested, "long_name").unwrap(), "energy");
+
+        let read = Data::read_hdf5(&file).unwrap();
+        assert_eq!(read.energy, data.energy);
+        assert_eq!(read.nested.attrs, grid);
+
+        assert!(Data::validate_hdf5(&file).is_valid());
+    }
+}
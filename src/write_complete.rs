@@ -0,0 +1,101 @@
+//! Marker attribute recording that every field of a container was written
+//!
+//! Containers deriving [`crate::ContainerWrite`] with `#[hdf5(write_complete)]` set the
+//! [`ATTRIBUTE`] attribute of their group to `0` before writing any fields, and to `1` after
+//! all fields have been written. If the process is killed part way through a write, the marker
+//! stays at `0` (or is missing entirely for a new group).
+//!
+//! [`crate::ContainerWrite::write_hdf5_selection`] only writes one block of the container, and does not
+//! touch the marker: call [`clear`] before the first block and [`mark`] after the last one. A field of
+//! a `write_complete` container cannot be named [`ATTRIBUTE`].
+//!
+//! Containers deriving [`crate::ContainerRead`] with `#[hdf5(write_complete)]` check the marker
+//! before reading any fields, and return [`crate::Error::IncompleteWrite`] if it is not set.
+//!
+//! ```
+//! use hdf5_derive::{ContainerRead, ContainerWrite};
+//! use ndarray::Array2;
+//!
+//! #[derive(ContainerRead, ContainerWrite)]
+//! #[hdf5(write_complete)]
+//! struct Solution {
+//!     velocity: Array2<f64>,
+//! }
+//!
+//! let path = "./write_complete_doc.h5";
+//! let file = hdf5_derive::File::create(path).unwrap();
+//!
+//! // a write that was interrupted before any fields were written
+//! let partial = file.create_group("partial").unwrap();
+//! hdf5_derive::write_complete::clear(&partial).unwrap();
+//! assert!(matches!(
+//!     Solution::read_hdf5(&partial),
+//!     Err(hdf5_derive::Error::IncompleteWrite(_))
+//! ));
+//!
+//! let solution = Solution { velocity: Array2::zeros((3, 3)) };
+//! solution.write_hdf5(&file).unwrap();
+//! assert!(hdf5_derive::write_complete::is_complete(&file));
+//! assert!(Solution::read_hdf5(&file).is_ok());
+//!
+//! std::fs::remove_file(path).unwrap();
+//! ```
+
+use hdf5::Group;
+
+use crate::error;
+use crate::Error;
+
+/// name of the marker attribute on the container group
+pub const ATTRIBUTE: &str = "write_complete";
+
+/// whether or not the marker on `group` is set. A missing or unreadable marker is not set
+pub fn is_complete(group: &Group) -> bool {
+    group
+        .attr(ATTRIBUTE)
+        .and_then(|attr| attr.read_scalar::<u8>())
+        .map(|value| value == 1)
+        .unwrap_or(false)
+}
+
+/// return [`Error::IncompleteWrite`] if the marker on `group` is not set
+pub fn check(group: &Group) -> Result<(), Error> {
+    if is_complete(group) {
+        Ok(())
+    } else {
+        Err(error::IncompleteWrite::new(&group.name()).into())
+    }
+}
+
+/// unset the marker on `group`, creating the attribute if it does not exist. Called
+/// before writing the fields of a container
+pub fn clear(group: &Group) -> Result<(), Error> {
+    write_marker(group, 0)
+}
+
+/// set the marker on `group`. Called after all fields of a container have been written
+pub fn mark(group: &Group) -> Result<(), Error> {
+    write_marker(group, 1)
+}
+
+fn write_marker(group: &Group, value: u8) -> Result<(), Error> {
+    let attribute = if group
+        .attr_names()
+        .is_ok_and(|names| names.iter().any(|n| n == ATTRIBUTE))
+    {
+        group
+            .attr(ATTRIBUTE)
+            .map_err(|e| error::FetchAttribute::from_field_name(ATTRIBUTE, e))?
+    } else {
+        group
+            .new_attr::<u8>()
+            .create(ATTRIBUTE)
+            .map_err(|e| error::CreateAttribute::from_field_name(ATTRIBUTE, e))?
+    };
+
+    attribute
+        .write_scalar(&value)
+        .map_err(|e| error::WriteAttribute::from_field_name(ATTRIBUTE, e))?;
+
+    Ok(())
+}
@@ -234,7 +234,7 @@ fn mutate_on_write_different_shapes() {
     mutate_writer.one = arr_another.clone();
     let out = mutate_writer.write_hdf5(&file);
 
-    assert!(out.is_err());
+    assert!(matches!(out, Err(hdf5_derive::Error::ShapeMismatch(_))));
 
     fs::remove_file(path).ok();
 }
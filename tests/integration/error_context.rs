@@ -0,0 +1,93 @@
+use hdf5_derive::{ContainerRead, ContainerWrite, Error};
+use std::fs;
+
+use ndarray::{Array2, Array4};
+
+#[derive(ContainerRead, ContainerWrite)]
+struct NestedData {
+    vorticity: Array4<f32>,
+}
+
+#[derive(ContainerRead, ContainerWrite)]
+struct Data {
+    pressure: Array2<f64>,
+    supplemental_information: NestedData,
+}
+
+#[derive(ContainerRead, ContainerWrite)]
+#[hdf5(mutate_on_write)]
+struct Mutated {
+    #[hdf5(rename(both = "extra"))]
+    supplemental_information: NestedData,
+}
+
+#[test]
+fn nested_read_failure() {
+    let path = "error_context_nested_read.h5";
+    fs::remove_file(path).ok();
+
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    let data = Data {
+        pressure: Array2::zeros((2, 2)),
+        supplemental_information: NestedData {
+            vorticity: Array4::zeros((2, 2, 2, 3)),
+        },
+    };
+    data.write_hdf5(&file).unwrap();
+    file.unlink("supplemental_information/vorticity").unwrap();
+
+    let error = Data::read_hdf5(&file).err().unwrap();
+
+    assert!(matches!(error, Error::MissingDataset(_)));
+    assert_eq!(error.path(), Some("/supplemental_information/vorticity"));
+    assert_eq!(
+        error.field_path().as_deref(),
+        Some("supplemental_information.vorticity")
+    );
+    assert_eq!(error.file_name(), Some(path));
+
+    let message = error.to_string();
+    assert!(message.contains("`/supplemental_information/vorticity`"));
+    assert!(message.contains(path));
+
+    drop(file);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn nested_write_failure() {
+    let path = "error_context_nested_write.h5";
+    fs::remove_file(path).ok();
+
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    // the group to mutate does not exist
+    let mutated = Mutated {
+        supplemental_information: NestedData {
+            vorticity: Array4::zeros((2, 2, 2, 3)),
+        },
+    };
+    let error = mutated.write_hdf5(&file).err().unwrap();
+
+    assert!(matches!(error, Error::MissingGroup(_)));
+    assert_eq!(error.path(), Some("/extra"));
+    assert_eq!(
+        error.field_path().as_deref(),
+        Some("supplemental_information")
+    );
+
+    drop(file);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn no_context_outside_containers() {
+    let error = Data::read_hdf5_from_path("error_context_does_not_exist.h5")
+        .err()
+        .unwrap();
+
+    assert!(matches!(error, Error::OpenFile(_)));
+    assert!(error.context().is_empty());
+    assert_eq!(error.path(), None);
+}
@@ -0,0 +1,53 @@
+use hdf5_derive::{ContainerRead, ContainerWrite};
+use std::fs;
+
+use ndarray::Array2;
+use ndarray::Array3;
+
+#[derive(ContainerRead, ContainerWrite, PartialEq, Debug)]
+struct Nested {
+    two: Array2<u32>,
+}
+
+#[derive(ContainerRead, ContainerWrite, PartialEq, Debug)]
+#[hdf5(lazy_twin = "CheckpointLazy")]
+struct Checkpoint {
+    #[hdf5(transpose = "both")]
+    velocity: Array3<f64>,
+    #[hdf5(rename(both = "re"))]
+    reynolds_number: f64,
+    nested: Nested,
+}
+
+#[test]
+fn lazy_twin_load() {
+    let path = "lazy_twin_load.h5";
+    fs::remove_file(path).ok();
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    let shape = (2, 3, 4);
+    let velocity = ndarray::Array::linspace(0., 1., shape.0 * shape.1 * shape.2)
+        .into_shape(shape)
+        .unwrap();
+
+    let checkpoint = Checkpoint {
+        velocity: velocity.clone(),
+        reynolds_number: 1650.,
+        nested: Nested {
+            two: Array2::ones((3, 3)),
+        },
+    };
+    checkpoint.write_hdf5(&file).unwrap();
+
+    let lazy = CheckpointLazy::read_hdf5(&file).unwrap();
+
+    // the lazy array sees the data as it is stored on disk
+    assert_eq!(lazy.velocity.shape(), &[4, 3, 2]);
+    assert_eq!(lazy.reynolds_number, 1650.);
+    assert!(!lazy.nested.is_loaded());
+
+    let loaded = lazy.load().unwrap();
+    assert_eq!(loaded, checkpoint);
+
+    fs::remove_file(path).ok();
+}
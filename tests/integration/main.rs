@@ -1,3 +1,9 @@
 mod array_read;
 mod array_write;
 mod attributes;
+mod error_context;
+mod lazy_twin;
+mod mismatch;
+mod read_report;
+mod selection;
+mod write_complete;
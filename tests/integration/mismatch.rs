@@ -0,0 +1,131 @@
+use hdf5_derive::error::ErrorKind;
+use hdf5_derive::{ContainerRead, ContainerWrite, Error};
+use std::fs;
+
+use ndarray::{Array2, Array3};
+
+#[derive(ContainerRead, ContainerWrite)]
+struct TwoDimensional {
+    pressure: Array2<f64>,
+}
+
+#[derive(ContainerRead, ContainerWrite)]
+struct ThreeDimensional {
+    pressure: Array3<f64>,
+}
+
+#[derive(ContainerRead)]
+struct SinglePrecision {
+    pressure: Array2<f32>,
+}
+
+#[derive(ContainerWrite)]
+#[hdf5(mutate_on_write)]
+struct Mutate {
+    pressure: Array2<f64>,
+}
+
+fn create(path: &str) -> hdf5_derive::File {
+    fs::remove_file(path).ok();
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    TwoDimensional {
+        pressure: Array2::ones((4, 3)),
+    }
+    .write_hdf5(&file)
+    .unwrap();
+
+    file
+}
+
+#[test]
+fn dimension_mismatch() {
+    let path = "mismatch_dimension.h5";
+    let file = create(path);
+
+    let error = ThreeDimensional::read_hdf5(&file).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::DimensionMismatch);
+
+    match &error {
+        Error::DimensionMismatch(mismatch) => {
+            assert_eq!(mismatch.array_name(), "pressure");
+            assert_eq!(mismatch.expected(), 3);
+            assert_eq!(mismatch.actual(), 2);
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+
+    assert_eq!(error.path(), Some("/pressure"));
+
+    drop(file);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn datatype_mismatch() {
+    let path = "mismatch_datatype.h5";
+    fs::remove_file(path).ok();
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    file.new_dataset::<hdf5::types::FixedAscii<8>>()
+        .shape((4, 3))
+        .create("pressure")
+        .unwrap();
+
+    let error = TwoDimensional::read_hdf5(&file).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::DatatypeMismatch);
+
+    match &error {
+        Error::WrongDatatype(mismatch) => {
+            assert_eq!(mismatch.array_name(), "pressure");
+            assert_eq!(
+                mismatch.expected(),
+                &hdf5::types::TypeDescriptor::Float(hdf5::types::FloatSize::U8)
+            );
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+
+    drop(file);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn convertible_datatypes_still_read() {
+    let path = "mismatch_convertible.h5";
+    let file = create(path);
+
+    // f64 data can be converted to f32 by HDF5
+    let read = SinglePrecision::read_hdf5(&file).unwrap();
+    assert_eq!(read.pressure, Array2::<f32>::ones((4, 3)));
+
+    drop(file);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn shape_mismatch_on_mutate() {
+    let path = "mismatch_shape.h5";
+    let file = create(path);
+
+    let mutate = Mutate {
+        pressure: Array2::zeros((2, 3)),
+    };
+    let error = mutate.write_hdf5(&file).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::ShapeMismatch);
+
+    match &error {
+        Error::ShapeMismatch(mismatch) => {
+            assert_eq!(mismatch.expected(), &[2, 3]);
+            assert_eq!(mismatch.actual(), &[4, 3]);
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+
+    // the data in the file is untouched
+    let read = TwoDimensional::read_hdf5(&file).unwrap();
+    assert_eq!(read.pressure, Array2::ones((4, 3)));
+
+    drop(file);
+    fs::remove_file(path).unwrap();
+}
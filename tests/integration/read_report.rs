@@ -0,0 +1,169 @@
+use hdf5_derive::error::ErrorKind;
+use hdf5_derive::{CancellationToken, ContainerRead, ContainerWrite, Error};
+use std::fs;
+
+use ndarray::{Array1, Array2, Array3};
+
+#[derive(ContainerRead, ContainerWrite)]
+struct NestedData {
+    vorticity: Array3<f32>,
+    energy: Array1<f64>,
+}
+
+#[derive(ContainerRead, ContainerWrite)]
+#[hdf5(write_complete)]
+struct Data {
+    pressure: Array2<f64>,
+    timestep: u64,
+    supplemental_information: NestedData,
+}
+
+fn aggregate(error: Error) -> hdf5_derive::error::Aggregate {
+    match error {
+        Error::Aggregate(aggregate) => aggregate,
+        other => panic!("expected an aggregate error, got {other}"),
+    }
+}
+
+#[test]
+fn reads_valid_file() {
+    let path = "read_report_valid.h5";
+    fs::remove_file(path).ok();
+
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    let data = Data {
+        pressure: Array2::zeros((2, 2)),
+        timestep: 10,
+        supplemental_information: NestedData {
+            vorticity: Array3::zeros((2, 2, 2)),
+            energy: Array1::zeros(4),
+        },
+    };
+    data.write_hdf5(&file).unwrap();
+
+    let read = Data::read_hdf5_report(&file).unwrap();
+    assert_eq!(read.timestep, 10);
+    assert_eq!(
+        read.supplemental_information.energy,
+        Array1::<f64>::zeros(4)
+    );
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn collects_every_error() {
+    let path = "read_report_every_error.h5";
+    fs::remove_file(path).ok();
+
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    let data = Data {
+        pressure: Array2::zeros((2, 2)),
+        timestep: 10,
+        supplemental_information: NestedData {
+            vorticity: Array3::zeros((2, 2, 2)),
+            energy: Array1::zeros(4),
+        },
+    };
+    data.write_hdf5(&file).unwrap();
+
+    file.unlink("pressure").unwrap();
+    file.unlink("supplemental_information/vorticity").unwrap();
+    file.unlink("supplemental_information/energy").unwrap();
+    file.new_dataset::<f64>()
+        .shape((2, 2))
+        .create("supplemental_information/energy")
+        .unwrap();
+
+    let aggregate = aggregate(Data::read_hdf5_report(&file).err().unwrap());
+
+    let errors: Vec<(ErrorKind, Option<&str>, Option<String>)> = aggregate
+        .errors()
+        .iter()
+        .map(|e| (e.kind(), e.path(), e.field_path()))
+        .collect();
+
+    assert_eq!(
+        errors,
+        [
+            (
+                ErrorKind::NotFound,
+                Some("/pressure"),
+                Some("pressure".to_string())
+            ),
+            (
+                ErrorKind::NotFound,
+                Some("/supplemental_information/vorticity"),
+                Some("supplemental_information.vorticity".to_string())
+            ),
+            (
+                ErrorKind::DimensionMismatch,
+                Some("/supplemental_information/energy"),
+                Some("supplemental_information.energy".to_string())
+            ),
+        ]
+    );
+
+    // every failure is part of the message
+    let message = aggregate.to_string();
+    assert!(message.starts_with("3 fields could not be read"));
+    assert!(message.contains("/supplemental_information/energy"));
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn incomplete_write_is_collected() {
+    let path = "read_report_incomplete.h5";
+    fs::remove_file(path).ok();
+
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    let data = Data {
+        pressure: Array2::zeros((2, 2)),
+        timestep: 10,
+        supplemental_information: NestedData {
+            vorticity: Array3::zeros((2, 2, 2)),
+            energy: Array1::zeros(4),
+        },
+    };
+    data.write_hdf5(&file).unwrap();
+
+    hdf5_derive::write_complete::clear(&file).unwrap();
+    file.unlink("pressure").unwrap();
+
+    let aggregate = aggregate(Data::read_hdf5_report(&file).err().unwrap());
+
+    let kinds: Vec<ErrorKind> = aggregate.errors().iter().map(Error::kind).collect();
+    assert_eq!(kinds, [ErrorKind::IncompleteWrite, ErrorKind::NotFound]);
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cancellation_is_not_collected() {
+    let path = "read_report_cancelled.h5";
+    fs::remove_file(path).ok();
+
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    let data = Data {
+        pressure: Array2::zeros((2, 2)),
+        timestep: 10,
+        supplemental_information: NestedData {
+            vorticity: Array3::zeros((2, 2, 2)),
+            energy: Array1::zeros(4),
+        },
+    };
+    data.write_hdf5(&file).unwrap();
+
+    let token = CancellationToken::new();
+    token.cancel();
+
+    let result = token.run(|| Data::read_hdf5_report(&file));
+    assert!(matches!(result, Err(Error::Cancelled(_))));
+
+    fs::remove_file(path).unwrap();
+}
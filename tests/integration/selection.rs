@@ -0,0 +1,239 @@
+use hdf5_derive::error::ErrorKind;
+use hdf5_derive::{ContainerRead, ContainerWrite, Error, Group, Slice};
+use std::fs;
+
+use ndarray::s;
+use ndarray::Array1;
+use ndarray::Array3;
+
+#[derive(ContainerRead, ContainerWrite)]
+struct NestedSelection {
+    two: Array3<u32>,
+}
+
+#[derive(ContainerRead, ContainerWrite)]
+struct Subdomain {
+    one: Array3<u32>,
+    #[hdf5(transpose = "both")]
+    transposed: Array3<u32>,
+    #[hdf5(select = false)]
+    grid: Array1<f64>,
+    timestep: u64,
+    nested: NestedSelection,
+}
+
+#[test]
+fn read_leading_axis_selection() {
+    let path = "read_leading_axis_selection.h5";
+    fs::remove_file(path).ok();
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    let shape = (6, 3, 2);
+    let arr = Array3::from_shape_fn(shape, |(i, j, k)| (i * 100 + j * 10 + k) as u32);
+
+    let data = Subdomain {
+        one: arr.clone(),
+        transposed: arr.clone(),
+        grid: Array1::linspace(0., 1., 6),
+        timestep: 10,
+        nested: NestedSelection { two: arr.clone() },
+    };
+    data.write_hdf5(&file).unwrap();
+
+    let subdomain = Subdomain::read_hdf5_selection(&file, Slice::from(2..4)).unwrap();
+
+    assert_eq!(subdomain.one, arr.slice(s![2..4, .., ..]));
+    assert_eq!(subdomain.transposed, arr.slice(s![2..4, .., ..]));
+    assert_eq!(subdomain.grid, data.grid);
+    assert_eq!(subdomain.timestep, 10);
+    assert_eq!(subdomain.nested.two, arr.slice(s![2..4, .., ..]));
+
+    fs::remove_file(path).ok();
+}
+
+#[derive(ContainerRead, ContainerWrite)]
+#[hdf5(select = false)]
+struct OptIn {
+    #[hdf5(select = true)]
+    one: Array3<u32>,
+    two: Array3<u32>,
+}
+
+#[test]
+fn read_selection_opt_in() {
+    let path = "read_selection_opt_in.h5";
+    fs::remove_file(path).ok();
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    let arr = Array3::from_shape_fn((4, 2, 2), |(i, j, k)| (i * 100 + j * 10 + k) as u32);
+
+    let data = OptIn {
+        one: arr.clone(),
+        two: arr.clone(),
+    };
+    data.write_hdf5(&file).unwrap();
+
+    let subdomain = OptIn::read_hdf5_selection(&file, Slice::from(1..)).unwrap();
+
+    assert_eq!(subdomain.one, arr.slice(s![1.., .., ..]));
+    assert_eq!(subdomain.two, arr);
+
+    fs::remove_file(path).ok();
+}
+
+#[derive(ContainerRead, ContainerWrite)]
+struct Block {
+    one: Array3<u32>,
+    #[hdf5(transpose = "both")]
+    transposed: Array3<u32>,
+    #[hdf5(select = false, mutate_on_write)]
+    grid: Array1<f64>,
+    nested: NestedSelection,
+}
+
+#[test]
+fn write_blocks_into_global_datasets() {
+    let path = "write_blocks_into_global_datasets.h5";
+    fs::remove_file(path).ok();
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    let global = Array3::from_shape_fn((6, 3, 2), |(i, j, k)| (i * 100 + j * 10 + k) as u32);
+
+    // preallocate the global datasets
+    let empty = Block {
+        one: Array3::zeros((6, 3, 2)),
+        transposed: Array3::zeros((6, 3, 2)),
+        grid: Array1::zeros(6),
+        nested: NestedSelection {
+            two: Array3::zeros((6, 3, 2)),
+        },
+    };
+    file.new_dataset::<f64>().shape(6).create("grid").unwrap();
+    empty.write_hdf5(&file).unwrap();
+
+    // each block writes its own rows
+    for start in [0, 2, 4] {
+        let rows = global.slice(s![start..start + 2, .., ..]).to_owned();
+        let block = Block {
+            one: rows.clone(),
+            transposed: rows.clone(),
+            grid: Array1::linspace(0., 1., 6),
+            nested: NestedSelection { two: rows },
+        };
+        block.write_hdf5_selection(&file, start).unwrap();
+    }
+
+    let read = Block::read_hdf5(&file).unwrap();
+    assert_eq!(read.one, global);
+    assert_eq!(read.transposed, global);
+    assert_eq!(read.grid, Array1::linspace(0., 1., 6));
+    assert_eq!(read.nested.two, global);
+
+    fs::remove_file(path).ok();
+}
+
+#[derive(ContainerRead, ContainerWrite)]
+struct BlockWithScalar {
+    one: Array3<u32>,
+    timestep: u64,
+}
+
+#[test]
+fn write_blocks_with_scalar() {
+    let path = "write_blocks_with_scalar.h5";
+    fs::remove_file(path).ok();
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    file.new_dataset::<u32>()
+        .shape((4, 3, 2))
+        .create("one")
+        .unwrap();
+
+    // the first block creates `timestep`, the second overwrites it
+    for start in [0, 2] {
+        let block = BlockWithScalar {
+            one: Array3::from_elem((2, 3, 2), start as u32),
+            timestep: 10,
+        };
+        block.write_hdf5_selection(&file, start).unwrap();
+    }
+
+    let read = BlockWithScalar::read_hdf5(&file).unwrap();
+    assert_eq!(read.timestep, 10);
+    assert_eq!(
+        read.one.slice(s![2.., .., ..]),
+        Array3::from_elem((2, 3, 2), 2)
+    );
+
+    fs::remove_file(path).ok();
+}
+
+#[derive(ContainerRead, ContainerWrite)]
+#[hdf5(write_complete)]
+struct CompleteBlock {
+    one: Array3<u32>,
+}
+
+#[test]
+fn write_blocks_leave_marker() {
+    let file = hdf5_derive::memory::create_in_memory().unwrap();
+
+    file.new_dataset::<u32>()
+        .shape((4, 3, 2))
+        .create("one")
+        .unwrap();
+    hdf5_derive::write_complete::clear(&file).unwrap();
+
+    let block = CompleteBlock {
+        one: Array3::ones((2, 3, 2)),
+    };
+    block.write_hdf5_selection(&file, 0).unwrap();
+
+    // only half of the container has been written
+    assert!(!hdf5_derive::write_complete::is_complete(&file));
+    assert!(matches!(
+        CompleteBlock::read_hdf5(&file),
+        Err(Error::IncompleteWrite(_))
+    ));
+
+    block.write_hdf5_selection(&file, 2).unwrap();
+    hdf5_derive::write_complete::mark(&file).unwrap();
+
+    let read = CompleteBlock::read_hdf5(&file).unwrap();
+    assert_eq!(read.one, Array3::ones((4, 3, 2)));
+}
+
+/// written by hand, without the optional selection methods
+struct Manual {
+    value: u64,
+}
+
+impl ContainerRead for Manual {
+    fn read_hdf5(container: &Group) -> Result<Self, Error> {
+        let value = hdf5_derive::ReadGroup::read_group(container, "value", false)?;
+        Ok(Manual { value })
+    }
+}
+
+impl ContainerWrite for Manual {
+    fn write_hdf5(&self, container: &Group) -> Result<(), Error> {
+        hdf5_derive::WriteGroup::write_group(&self.value, container, "value", false, false)
+    }
+}
+
+#[test]
+fn manual_selection_unsupported() {
+    let file = hdf5_derive::memory::create_in_memory().unwrap();
+
+    let manual = Manual { value: 1 };
+    let error = manual.write_hdf5_selection(&file, 0).err().unwrap();
+
+    assert_eq!(error.kind(), ErrorKind::Unsupported);
+
+    manual.write_hdf5(&file).unwrap();
+    let error = Manual::read_hdf5_selection(&file, Slice::from(..))
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), ErrorKind::Unsupported);
+    assert_eq!(Manual::read_hdf5(&file).unwrap().value, 1);
+}
@@ -0,0 +1,93 @@
+use hdf5_derive::{ContainerRead, ContainerWrite, Error};
+use std::fs;
+
+use ndarray::Array2;
+
+#[derive(ContainerRead, ContainerWrite)]
+#[hdf5(write_complete)]
+struct Marked {
+    arr: Array2<u32>,
+    value: u64,
+}
+
+#[derive(ContainerRead, ContainerWrite)]
+#[hdf5(write_complete)]
+struct Outer {
+    nested: Marked,
+}
+
+#[derive(ContainerRead, ContainerWrite)]
+#[hdf5(write_complete, mutate_on_write)]
+struct Mutated {
+    arr: Array2<u32>,
+    value: u64,
+}
+
+#[test]
+fn marker_set_after_write() {
+    let path = "write_complete_marker_set.h5";
+    fs::remove_file(path).ok();
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    let outer = Outer {
+        nested: Marked {
+            arr: Array2::ones((2, 2)),
+            value: 3,
+        },
+    };
+    outer.write_hdf5(&file).unwrap();
+
+    assert!(hdf5_derive::write_complete::is_complete(&file));
+    assert!(hdf5_derive::write_complete::is_complete(
+        &file.group("nested").unwrap()
+    ));
+
+    let read = Outer::read_hdf5(&file).unwrap();
+    assert_eq!(read.nested.arr, outer.nested.arr);
+    assert_eq!(read.nested.value, 3);
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn interrupted_write_is_detected() {
+    let path = "write_complete_interrupted.h5";
+    fs::remove_file(path).ok();
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    let mutated = Mutated {
+        arr: Array2::ones((2, 2)),
+        value: 3,
+    };
+
+    // `arr` does not exist yet, so the write fails after the marker was cleared
+    file.new_attr::<u64>().create("value").unwrap();
+    assert!(mutated.write_hdf5(&file).is_err());
+    assert!(!hdf5_derive::write_complete::is_complete(&file));
+
+    // the datasets exist by the time the file is read, but the marker is still unset
+    file.new_dataset::<u32>()
+        .shape((2, 2))
+        .create("arr")
+        .unwrap();
+    let err = Mutated::read_hdf5(&file).err().unwrap();
+    assert!(matches!(err, Error::IncompleteWrite(_)));
+
+    // a complete write sets the marker again
+    mutated.write_hdf5(&file).unwrap();
+    assert!(Mutated::read_hdf5(&file).is_ok());
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn missing_marker_is_rejected() {
+    let path = "write_complete_missing.h5";
+    fs::remove_file(path).ok();
+    let file = hdf5_derive::File::create(path).unwrap();
+
+    let err = Outer::read_hdf5(&file).err().unwrap();
+    assert!(matches!(err, Error::IncompleteWrite(_)));
+
+    fs::remove_file(path).unwrap();
+}
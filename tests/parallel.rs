@@ -0,0 +1,102 @@
+//! Collective reads and writes with the MPI-IO driver. This binary runs without the test harness, so
+//! `cargo test --features mpi` runs it on a single rank. To run it on several ranks:
+//!
+//! ```bash
+//! cargo test --features mpi --test parallel --no-run
+//! mpirun -n 4 target/debug/deps/parallel-<hash>
+//! ```
+use hdf5_derive::parallel::{self, ContainerReadParallel, ContainerWriteParallel, OpenMode};
+use hdf5_derive::{ContainerRead, ContainerWrite, Slice};
+
+use ndarray::{Array1, Array2};
+
+#[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+struct Diagnostics {
+    energy: Array1<f64>,
+}
+
+#[derive(ContainerRead, ContainerWrite, Debug, PartialEq)]
+struct Block {
+    velocity: Array2<f64>,
+    #[hdf5(transpose = "both")]
+    pressure: Array2<f64>,
+    diagnostics: Diagnostics,
+    #[hdf5(select = false)]
+    grid: Array1<f64>,
+    timestep: u64,
+}
+
+fn block(rank: usize, rows: usize) -> Block {
+    let start = (rank * rows) as f64;
+
+    Block {
+        velocity: Array2::from_shape_fn((rows, 3), |(i, j)| start + i as f64 + j as f64 / 10.),
+        pressure: Array2::from_shape_fn((rows, 2), |(i, j)| -(start + i as f64) - j as f64),
+        diagnostics: Diagnostics {
+            energy: Array1::from_shape_fn(rows, |i| start + i as f64),
+        },
+        grid: Array1::linspace(0., 1., 5),
+        timestep: 100,
+    }
+}
+
+/// MPI can only be initialized once per process, and only from the main thread, so this binary runs
+/// without the test harness
+fn main() {
+    unsafe {
+        mpi_sys::MPI_Init(std::ptr::null_mut(), std::ptr::null_mut());
+    }
+
+    let comm = unsafe { mpi_sys::RSMPI_COMM_WORLD };
+    let (mut rank, mut size) = (0, 0);
+    unsafe {
+        mpi_sys::MPI_Comm_rank(comm, &mut rank);
+        mpi_sys::MPI_Comm_size(comm, &mut size);
+    }
+    let (rank, size) = (rank as usize, size as usize);
+
+    write_and_read_decomposed_container(comm, rank, size);
+
+    unsafe {
+        mpi_sys::MPI_Finalize();
+    }
+
+    if rank == 0 {
+        println!("parallel: all tests passed on {size} ranks");
+    }
+}
+
+fn write_and_read_decomposed_container(comm: parallel::MPI_Comm, rank: usize, size: usize) {
+    let path = "./parallel_write_and_read.h5";
+    let rows = 4;
+
+    let file = parallel::open_parallel(path, OpenMode::Create, comm).unwrap();
+    let local = block(rank, rows);
+    local
+        .write_hdf5_parallel(&file, rank * rows, size * rows)
+        .unwrap();
+    file.close().unwrap();
+
+    let file = parallel::open_parallel(path, OpenMode::Read, comm).unwrap();
+    assert_eq!(
+        file.dataset("velocity").unwrap().shape(),
+        vec![size * rows, 3]
+    );
+    assert_eq!(
+        file.dataset("pressure").unwrap().shape(),
+        vec![2, size * rows]
+    );
+
+    let start = rank * rows;
+    let read = Block::read_hdf5_parallel(&file, Slice::from(start..start + rows)).unwrap();
+    assert_eq!(read, local);
+    file.close().unwrap();
+
+    unsafe {
+        mpi_sys::MPI_Barrier(comm);
+    }
+
+    if rank == 0 {
+        std::fs::remove_file(path).unwrap();
+    }
+}